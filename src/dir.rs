@@ -4,13 +4,25 @@
 // Imports
 // ============================================================================
 
-// None
+use crate::file::Attributes;
+use crate::path::{Path, PathBuf};
 
 // ============================================================================
 // Constants
 // ============================================================================
 
-// None
+/// The most components a [`Glob`] pattern can have after the last fixed
+/// directory.
+///
+/// Each one needs an open directory handle while the pattern is expanded.
+pub const MAX_GLOB_DEPTH: usize = 8;
+
+/// The deepest a [`Walker`] will go below the directory it starts in.
+pub const MAX_WALK_DEPTH: usize = 16;
+
+/// How many directory handles a [`Walker`] keeps open, unless you say
+/// otherwise with [`Walker::max_open_handles`].
+pub const DEFAULT_WALK_HANDLES: usize = 4;
 
 // ============================================================================
 // Types
@@ -37,33 +49,1468 @@ impl Handle {
     }
 }
 
+/// Represents a position within an open directory.
+///
+/// Returned by [`Api::telldir`](crate::Api::telldir), and accepted by
+/// [`Api::seekdir`](crate::Api::seekdir). The value has no meaning outside of
+/// the OS, and is only valid for the open handle it came from.
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Position(u64);
+
+impl Position {
+    /// Construct a new `Position` from an integer.
+    ///
+    /// Only the OS should call this - applications should only use the
+    /// values they get from `Api::telldir`.
+    pub const fn new(value: u64) -> Position {
+        Position(value)
+    }
+
+    /// Get the numeric value of this Position
+    pub const fn value(&self) -> u64 {
+        self.0
+    }
+}
+
 /// Describes an entry in a directory.
 ///
 /// This is set up for 8.3 filenames on MS-DOS FAT32 partitions currently.
 #[repr(C)]
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Entry {
     /// The name and extension of the file.
     ///
-    /// The name and extension are separated by a single '.'.
+    /// The name and extension are separated by a single '.', and the name is
+    /// left-aligned in the array. Any unused bytes at the end are set to zero
+    /// (`b'\0'`). A name with no extension has no '.'. Use
+    /// [`Entry::name_str`] rather than reading this field directly.
     ///
-    /// The filename will be in ASCII. Unicode filenames are not supported.
+    /// The filename will be in ASCII. Unicode filenames are not supported
+    /// here - use [`Api::readdir_long`](crate::Api::readdir_long) to get the
+    /// long filename as well.
     pub name: [u8; crate::MAX_FILENAME_LEN],
     /// The properties for the file/directory this entry represents.
     pub properties: crate::file::Stat,
 }
 
+impl Entry {
+    /// The name of the entry for the directory itself.
+    const DOT: &'static str = ".";
+
+    /// The name of the entry for the parent directory.
+    const DOT_DOT: &'static str = "..";
+
+    /// Construct a new `Entry`.
+    ///
+    /// The `name` must be a valid 8.3 filename - one to eight characters, and
+    /// optionally a `.` followed by one to three characters. Only the
+    /// characters permitted in FAT short names (see [`crate::path::Path`])
+    /// are allowed. The special names `.` and `..` are also accepted.
+    ///
+    /// Short names are always upper-case on disk, so lower-case letters are
+    /// converted - `readme.txt` is stored as `README.TXT`.
+    ///
+    /// If the name is not valid, you get `Err(Error::InvalidPath)`.
+    pub fn new(name: &str, properties: crate::file::Stat) -> Result<Entry, crate::Error> {
+        Ok(Entry {
+            name: encode_name(name)?,
+            properties,
+        })
+    }
+
+    /// Get the name of this entry, including the extension (if any).
+    ///
+    /// Any padding bytes are removed. If the name is not valid UTF-8, you get
+    /// the part of the name before the first invalid byte.
+    pub fn name_str(&self) -> &str {
+        decode_name(&self.name)
+    }
+
+    /// Get the name of this entry, without the extension.
+    ///
+    /// * An entry called `README.TXT` has a basename of `README`.
+    /// * An entry called `BOOTLDR` has a basename of `BOOTLDR`.
+    pub fn basename(&self) -> &str {
+        let name = self.name_str();
+        if name == Self::DOT || name == Self::DOT_DOT {
+            return name;
+        }
+        match name.split_once('.') {
+            Some((basename, _extension)) => basename,
+            None => name,
+        }
+    }
+
+    /// Get the extension of this entry, if any.
+    ///
+    /// * An entry called `README.TXT` has an extension of `TXT`.
+    /// * An entry called `BOOTLDR` has no extension.
+    pub fn extension(&self) -> Option<&str> {
+        let name = self.name_str();
+        if name == Self::DOT || name == Self::DOT_DOT {
+            return None;
+        }
+        name.split_once('.').map(|(_basename, extension)| extension)
+    }
+
+    /// Is this the `.` or `..` entry?
+    pub fn is_dot_entry(&self) -> bool {
+        let name = self.name_str();
+        name == Self::DOT || name == Self::DOT_DOT
+    }
+}
+
+/// A mutable slice of [`Entry`] values, but compatible with FFI.
+///
+/// Passed to [`Api::readdir_many`](crate::Api::readdir_many) so the OS can
+/// return many directory entries in one call. Assume the lifetime is only
+/// valid until the callee returns to the caller.
+#[repr(C)]
+#[derive(Debug)]
+pub struct EntryBuffer<'a> {
+    /// A pointer to where the entries can be put
+    pub data: *mut Entry,
+    /// The maximum number of entries we can store in this buffer
+    pub data_len: usize,
+    /// A phantom object to hold the lifetime
+    _phantom: core::marker::PhantomData<&'a mut [Entry]>,
+}
+
+impl<'a> EntryBuffer<'a> {
+    /// Create a new buffer we can send over the FFI.
+    pub fn new(s: &'a mut [Entry]) -> EntryBuffer<'a> {
+        EntryBuffer {
+            data: s.as_mut_ptr(),
+            data_len: s.len(),
+            _phantom: core::marker::PhantomData,
+        }
+    }
+
+    /// Turn this buffer into a Rust mutable slice.
+    ///
+    /// You will get `None` if the buffer has no storage.
+    pub fn as_mut_slice(&mut self) -> Option<&mut [Entry]> {
+        if self.data.is_null() {
+            None
+        } else {
+            // SAFETY: `data` and `data_len` came from the `&'a mut [Entry]`
+            // given to `new`, so they describe initialised `Entry` values we
+            // have exclusive access to for `'a`. Taking `&mut self` means we
+            // hand out only one slice at a time. Anyone who writes to the
+            // public fields must keep these rules, as with `FfiBuffer`.
+            Some(unsafe { core::slice::from_raw_parts_mut(self.data, self.data_len) })
+        }
+    }
+}
+
+impl<'a> From<&'a mut [Entry]> for EntryBuffer<'a> {
+    /// Convert from a Rust slice into an FFI compatible slice
+    fn from(input: &'a mut [Entry]) -> EntryBuffer<'a> {
+        EntryBuffer::new(input)
+    }
+}
+
+/// Describes an entry in a directory which may have a long filename.
+///
+/// Returned by [`Api::readdir_long`](crate::Api::readdir_long). The long
+/// filename itself is written to a buffer supplied by the caller.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LongEntry {
+    /// The short (8.3) name and properties of this entry.
+    pub entry: Entry,
+    /// The number of bytes of UTF-8 written to the long filename buffer.
+    ///
+    /// This is zero if the entry only has a short name.
+    pub long_name_len: usize,
+}
+
+impl LongEntry {
+    /// Get the long filename from the buffer that was passed to
+    /// [`Api::readdir_long`](crate::Api::readdir_long).
+    ///
+    /// You get `None` if this entry has no long filename, or if the buffer
+    /// does not contain valid UTF-8.
+    pub fn long_name<'b>(&self, buffer: &'b [u8]) -> Option<&'b str> {
+        if self.long_name_len == 0 {
+            return None;
+        }
+        let bytes = buffer.get(..self.long_name_len)?;
+        core::str::from_utf8(bytes).ok()
+    }
+}
+
+/// Expands a DOS-style wildcard pattern into the matching directory entries.
+///
+/// Wildcards work as described in [`crate::path::wildcard_match`], and may
+/// appear in directory components as well as in the filename, so
+/// `HD0:/*/REPORT??.DOC` finds matching reports in every top-level
+/// directory. A pattern ending in `/` only matches directories.
+///
+/// Only the short (8.3) names of entries are matched. The `.` and `..` entries
+/// and volume labels are never matched.
+///
+/// Every match is returned with its absolute path, which must fit in `N`
+/// bytes. Directories have a trailing `/`.
+///
+/// ```no_run
+/// # use neotron_api::{Api, dir::Glob};
+/// # fn example(api: &Api) -> Result<(), neotron_api::Error> {
+/// for item in Glob::<64>::new(api, "HD0:/DOCS/*.TXT")? {
+///     let item = item?;
+///     // ... process item.path ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Glob<'a, const N: usize> {
+    api: &'a crate::Api,
+    /// The part of the pattern after the fixed base directory
+    pattern: &'a str,
+    /// How many components are in `pattern`
+    num_components: usize,
+    /// Only yield directories
+    dirs_only: bool,
+    /// One open directory for each component we are currently matching
+    handles: [Handle; MAX_GLOB_DEPTH],
+    /// How many entries of `handles` are open
+    depth: usize,
+    /// The path of the directory open in the last entry of `handles`
+    path: PathBuf<N>,
+}
+
+impl<'a, const N: usize> Glob<'a, N> {
+    /// Start expanding a wildcard pattern.
+    ///
+    /// Relative patterns are taken relative to the current directory. The
+    /// directory before the first wildcard is opened straight away, so if it
+    /// doesn't exist you get an error here.
+    ///
+    /// If the pattern has more than [`MAX_GLOB_DEPTH`] components after that
+    /// directory, you get `Err(Error::InvalidArg)`.
+    pub fn new(api: &'a crate::Api, pattern: &'a str) -> Result<Glob<'a, N>, crate::Error> {
+        Path::new(pattern)?;
+        let trimmed = pattern.trim_end_matches(Path::PATH_SEP);
+        let dirs_only = trimmed.len() != pattern.len();
+        let first_wildcard = trimmed.find(['*', '?']).unwrap_or(trimmed.len());
+        let base_len = trimmed[..first_wildcard]
+            .rfind([Path::PATH_SEP, Path::DRIVE_SEP])
+            .map_or(0, |idx| idx + 1);
+        let (base, rest) = trimmed.split_at(base_len);
+        let mut num_components = 0;
+        for component in rest.split(Path::PATH_SEP).filter(|c| !c.is_empty()) {
+            if component == "." || component == ".." {
+                return Err(crate::Error::InvalidPath);
+            }
+            num_components += 1;
+        }
+        if num_components == 0 {
+            return Err(crate::Error::InvalidPath);
+        }
+        if num_components > MAX_GLOB_DEPTH {
+            return Err(crate::Error::InvalidArg);
+        }
+        let base = Path::new(if base.is_empty() { "./" } else { base })?;
+        let path: PathBuf<N> = base.absolute(api)?;
+        let handle: Handle = Result::from((api.opendir)(path.as_path().as_ffi_string()))?;
+        let mut handles = [Handle::new(0); MAX_GLOB_DEPTH];
+        handles[0] = handle;
+        Ok(Glob {
+            api,
+            pattern: rest,
+            num_components,
+            dirs_only,
+            handles,
+            depth: 1,
+            path,
+        })
+    }
+
+    /// Get the pattern component matched by the directory at the given depth.
+    fn component(&self, level: usize) -> &'a str {
+        self.pattern
+            .split(Path::PATH_SEP)
+            .filter(|c| !c.is_empty())
+            .nth(level)
+            .unwrap_or("")
+    }
+
+    /// Close the deepest open directory.
+    fn close_level(&mut self) {
+        self.depth -= 1;
+        let _ = (self.api.closedir)(self.handles[self.depth]);
+        if self.depth > 0 {
+            self.path.pop();
+        }
+    }
+
+    /// Descend into a matching directory.
+    fn open_level(&mut self, name: &str) -> Result<(), crate::Error> {
+        push_name(&mut self.path, name, true)?;
+        match Result::from((self.api.opendir)(self.path.as_path().as_ffi_string())) {
+            Ok(handle) => {
+                self.handles[self.depth] = handle;
+                self.depth += 1;
+                Ok(())
+            }
+            Err(e) => {
+                self.path.pop();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<'a, const N: usize> Iterator for Glob<'a, N> {
+    type Item = Result<GlobEntry<N>, crate::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.depth > 0 {
+            let level = self.depth - 1;
+            let entry: Entry = match Result::from((self.api.readdir)(self.handles[level])) {
+                Ok(entry) => entry,
+                Err(crate::Error::EndOfFile) => {
+                    self.close_level();
+                    continue;
+                }
+                Err(e) => {
+                    self.close_level();
+                    return Some(Err(e));
+                }
+            };
+            if entry.is_dot_entry() || entry.properties.is_volume_label() {
+                continue;
+            }
+            if !crate::path::wildcard_match(self.component(level), entry.name_str()) {
+                continue;
+            }
+            let is_dir = entry.properties.is_dir();
+            if level + 1 == self.num_components {
+                if self.dirs_only && !is_dir {
+                    continue;
+                }
+                let mut path = self.path.clone();
+                return Some(
+                    push_name(&mut path, entry.name_str(), is_dir)
+                        .map(|_| GlobEntry { path, entry }),
+                );
+            } else if is_dir {
+                if let Err(e) = self.open_level(entry.name_str()) {
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a, const N: usize> Drop for Glob<'a, N> {
+    fn drop(&mut self) {
+        while self.depth > 0 {
+            self.close_level();
+        }
+    }
+}
+
+/// A directory entry found by a [`Glob`].
+#[derive(Clone, Debug)]
+pub struct GlobEntry<const N: usize> {
+    /// The absolute path of the entry.
+    ///
+    /// Directories have a trailing `/`.
+    pub path: PathBuf<N>,
+    /// The directory entry itself.
+    pub entry: Entry,
+}
+
+/// Selects which entries a [`ReadDir`] returns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter<'p> {
+    /// Only entries with a short name matching this wildcard pattern are
+    /// returned.
+    ///
+    /// See [`crate::path::wildcard_match`].
+    pub pattern: &'p str,
+    /// Entries with any of these attributes are skipped.
+    pub exclude: Attributes,
+    /// Should the `.` and `..` entries be returned (if they match
+    /// `pattern`)?
+    pub dot_entries: bool,
+}
+
+impl<'p> Filter<'p> {
+    /// Make a filter which works like the MS-DOS `DIR` command.
+    ///
+    /// Entries matching the pattern are returned, except for hidden and
+    /// system files, and volume labels.
+    pub const fn new(pattern: &'p str) -> Filter<'p> {
+        Filter {
+            pattern,
+            exclude: Attributes::HIDDEN
+                .union(Attributes::SYSTEM)
+                .union(Attributes::VOLUME),
+            dot_entries: true,
+        }
+    }
+
+    /// Make a filter which returns every entry.
+    pub const fn all() -> Filter<'static> {
+        Filter {
+            pattern: "*",
+            exclude: Attributes::empty(),
+            dot_entries: true,
+        }
+    }
+
+    /// Does this filter select the given entry?
+    pub fn matches(&self, entry: &Entry) -> bool {
+        if entry.properties.attr.intersects(self.exclude) {
+            return false;
+        }
+        if entry.is_dot_entry() && !self.dot_entries {
+            return false;
+        }
+        crate::path::wildcard_match(self.pattern, entry.name_str())
+    }
+}
+
+/// Reads the entries in a directory which match a [`Filter`].
+///
+/// The directory is closed when this object is dropped.
+///
+/// ```no_run
+/// # use neotron_api::{Api, dir::{Filter, ReadDir}};
+/// # fn example(api: &Api) -> Result<(), neotron_api::Error> {
+/// for entry in ReadDir::new(api, "HD0:/DOCS", Filter::new("*.TXT"))? {
+///     let entry = entry?;
+///     // ... process entry ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReadDir<'a, 'p> {
+    api: &'a crate::Api,
+    /// The open directory
+    handle: Handle,
+    /// Which entries to return
+    filter: Filter<'p>,
+    /// Have we reached the end (or hit an error)?
+    finished: bool,
+}
+
+impl<'a, 'p> ReadDir<'a, 'p> {
+    /// Open a directory for reading.
+    ///
+    /// If the directory cannot be opened, you get the error from
+    /// [`Api::opendir`](crate::Api::opendir).
+    pub fn new(
+        api: &'a crate::Api,
+        path: &str,
+        filter: Filter<'p>,
+    ) -> Result<ReadDir<'a, 'p>, crate::Error> {
+        let path = Path::new(path)?;
+        let handle = Result::from((api.opendir)(path.as_ffi_string()))?;
+        Ok(ReadDir {
+            api,
+            handle,
+            filter,
+            finished: false,
+        })
+    }
+
+    /// Go back to the first entry in the directory.
+    pub fn rewind(&mut self) -> Result<(), crate::Error> {
+        Result::from((self.api.rewinddir)(self.handle))?;
+        self.finished = false;
+        Ok(())
+    }
+
+    /// Read every remaining matching entry into the given slice.
+    ///
+    /// Returns how many entries were stored. If the slice fills up before the
+    /// end of the directory, you can call this again to get the rest.
+    pub fn read_into(&mut self, entries: &mut [Entry]) -> Result<usize, crate::Error> {
+        let mut count = 0;
+        for slot in entries.iter_mut() {
+            match self.next() {
+                Some(entry) => *slot = entry?,
+                None => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl<'a, 'p> Iterator for ReadDir<'a, 'p> {
+    type Item = Result<Entry, crate::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            match Result::from((self.api.readdir)(self.handle)) {
+                Ok(entry) if self.filter.matches(&entry) => return Some(Ok(entry)),
+                Ok(_) => {}
+                Err(crate::Error::EndOfFile) => self.finished = true,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a, 'p> Drop for ReadDir<'a, 'p> {
+    fn drop(&mut self) {
+        let _ = (self.api.closedir)(self.handle);
+    }
+}
+
+/// The ways [`sort_entries`] can order directory entries.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    /// By name, then by extension (ignoring case)
+    Name,
+    /// Smallest first
+    Size,
+    /// Oldest modification time first
+    Modified,
+}
+
+/// The order in which a [`Walker`] visits a directory tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WalkOrder {
+    /// Each directory is followed immediately by everything inside it.
+    DepthFirst,
+    /// Everything at one level is visited before anything at the next level
+    /// down.
+    ///
+    /// This is done without allocating, by walking the tree again for each
+    /// level, so it does more directory reads than `DepthFirst`.
+    BreadthFirst,
+}
+
+/// Visits every file and directory below a starting directory.
+///
+/// Entries are returned with their absolute path (which must fit in `N`
+/// bytes) and their properties. The `.` and `..` entries are skipped.
+///
+/// The OS only has a few directory handles, so the walker will only keep
+/// [`DEFAULT_WALK_HANDLES`] open at once. When it needs another, it closes
+/// the highest directory it has open. Once it has come back up, it opens that
+/// directory again and skips the entries it had already read. (A
+/// [`Position`] is only valid for the handle it came from, so we cannot use
+/// [`Api::seekdir`](crate::Api::seekdir) for this.) The tree should not be
+/// changed while it is being walked.
+///
+/// ```no_run
+/// # use neotron_api::{Api, dir::{WalkEntry, Walker, WalkOrder}, file::Attributes};
+/// # fn example(api: &Api) -> Result<(), neotron_api::Error> {
+/// let skip_hidden = |item: &WalkEntry<64>| item.stat.attr.contains(Attributes::HIDDEN);
+/// let walker = Walker::<64>::new(api, "HD0:/", WalkOrder::DepthFirst)?
+///     .max_depth(3)
+///     .prune(&skip_hidden);
+/// for item in walker {
+///     let item = item?;
+///     // ... process item.path ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Walker<'a, const N: usize> {
+    api: &'a crate::Api,
+    order: WalkOrder,
+    /// Entries this returns true for are skipped, along with their contents
+    prune: Option<&'a dyn Fn(&WalkEntry<N>) -> bool>,
+    /// How many handles we may have open
+    max_handles: usize,
+    /// Don't return anything deeper than this
+    max_depth: usize,
+    /// The depth of the entries returned on this pass (breadth-first only)
+    pass_depth: usize,
+    /// Did this pass find a directory at `pass_depth` worth looking inside?
+    found_more: bool,
+    /// The open directory at each level, or `None` if it had to be closed
+    handles: [Option<Handle>; MAX_WALK_DEPTH],
+    /// How many entries we have read from the directory at each level
+    read_counts: [u64; MAX_WALK_DEPTH],
+    /// How many levels we have below the start (including the start)
+    depth: usize,
+    /// How many entries of `handles` are `Some`
+    open: usize,
+    /// The path of the directory at the deepest level
+    path: PathBuf<N>,
+    /// A directory we found, which we will go into on the next call to `next`
+    descend: Option<[u8; crate::MAX_FILENAME_LEN]>,
+}
+
+impl<'a, const N: usize> Walker<'a, N> {
+    /// Start walking from the given directory.
+    ///
+    /// Relative paths are taken relative to the current directory. The
+    /// directory is opened straight away, so if it doesn't exist you get an
+    /// error here.
+    pub fn new(
+        api: &'a crate::Api,
+        path: &str,
+        order: WalkOrder,
+    ) -> Result<Walker<'a, N>, crate::Error> {
+        let path: PathBuf<N> = Path::new(path)?.absolute(api)?;
+        let handle = Result::from((api.opendir)(path.as_path().as_ffi_string()))?;
+        let mut handles = [None; MAX_WALK_DEPTH];
+        handles[0] = Some(handle);
+        Ok(Walker {
+            api,
+            order,
+            prune: None,
+            max_handles: DEFAULT_WALK_HANDLES,
+            max_depth: MAX_WALK_DEPTH,
+            pass_depth: 1,
+            found_more: false,
+            handles,
+            read_counts: [0; MAX_WALK_DEPTH],
+            depth: 1,
+            open: 1,
+            path,
+            descend: None,
+        })
+    }
+
+    /// Set how many directory handles can be open at once.
+    ///
+    /// This is at least one. Fewer handles means more work when coming back
+    /// up the tree.
+    pub fn max_open_handles(mut self, handles: usize) -> Self {
+        self.max_handles = handles.max(1);
+        self
+    }
+
+    /// Set how deep to go.
+    ///
+    /// The entries in the starting directory have a depth of one. Nothing
+    /// deeper than [`MAX_WALK_DEPTH`] is ever returned.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth.min(MAX_WALK_DEPTH);
+        self
+    }
+
+    /// Skip some parts of the tree.
+    ///
+    /// Any entry for which `prune` returns `true` is not returned, and if it
+    /// is a directory, nothing inside it is returned either. When walking
+    /// breadth-first, `prune` may be called more than once for the same
+    /// directory.
+    pub fn prune(mut self, prune: &'a dyn Fn(&WalkEntry<N>) -> bool) -> Self {
+        self.prune = Some(prune);
+        self
+    }
+
+    /// Close a handle, if we are at the limit.
+    ///
+    /// The highest open directory is closed.
+    fn make_room(&mut self) -> Result<(), crate::Error> {
+        if self.open < self.max_handles {
+            return Ok(());
+        }
+        let Some(level) = self.handles[..self.depth].iter().position(Option::is_some) else {
+            return Ok(());
+        };
+        let handle = self.handles[level].unwrap();
+        let _ = (self.api.closedir)(handle);
+        self.handles[level] = None;
+        self.open -= 1;
+        Ok(())
+    }
+
+    /// Get the handle for the deepest level, opening the directory again if
+    /// it had to be closed.
+    ///
+    /// When we open it again, we skip over the entries we have already read.
+    fn deepest_handle(&mut self) -> Result<Handle, crate::Error> {
+        let level = self.depth - 1;
+        if let Some(handle) = self.handles[level] {
+            return Ok(handle);
+        }
+        self.make_room()?;
+        let handle = Result::from((self.api.opendir)(self.path.as_path().as_ffi_string()))?;
+        self.handles[level] = Some(handle);
+        self.open += 1;
+        for _ in 0..self.read_counts[level] {
+            match Result::from((self.api.readdir)(handle)) {
+                Ok(_) => {}
+                // The directory got shorter, so there's nothing left to read
+                Err(crate::Error::EndOfFile) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(handle)
+    }
+
+    /// Go into a sub-directory of the deepest level.
+    fn open_level(&mut self, name: &str) -> Result<(), crate::Error> {
+        if self.depth == MAX_WALK_DEPTH {
+            return Err(crate::Error::InvalidArg);
+        }
+        self.make_room()?;
+        push_name(&mut self.path, name, true)?;
+        match Result::from((self.api.opendir)(self.path.as_path().as_ffi_string())) {
+            Ok(handle) => {
+                self.handles[self.depth] = Some(handle);
+                self.read_counts[self.depth] = 0;
+                self.depth += 1;
+                self.open += 1;
+                Ok(())
+            }
+            Err(e) => {
+                self.path.pop();
+                Err(e)
+            }
+        }
+    }
+
+    /// Close the deepest level.
+    fn close_level(&mut self) {
+        self.depth -= 1;
+        if let Some(handle) = self.handles[self.depth].take() {
+            let _ = (self.api.closedir)(handle);
+            self.open -= 1;
+        }
+        if self.depth > 0 {
+            self.path.pop();
+        }
+    }
+
+    /// We have read everything in the starting directory. Start the next
+    /// breadth-first pass, if there is one.
+    fn next_pass(&mut self) -> Result<bool, crate::Error> {
+        if self.order != WalkOrder::BreadthFirst || !self.found_more {
+            return Ok(false);
+        }
+        self.pass_depth += 1;
+        self.found_more = false;
+        self.read_counts[0] = 0;
+        match self.handles[0] {
+            Some(handle) => Result::from((self.api.rewinddir)(handle))?,
+            None => {
+                self.make_room()?;
+                let handle = Result::from((self.api.opendir)(self.path.as_path().as_ffi_string()))?;
+                self.handles[0] = Some(handle);
+                self.open += 1;
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<'a, const N: usize> Iterator for Walker<'a, N> {
+    type Item = Result<WalkEntry<N>, crate::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(name) = self.descend.take() {
+                if let Err(e) = self.open_level(decode_name(&name)) {
+                    return Some(Err(e));
+                }
+            }
+            if self.depth == 0 {
+                return None;
+            }
+            let result = self
+                .deepest_handle()
+                .and_then(|handle| Result::from((self.api.readdir)(handle)));
+            let entry = match result {
+                Ok(entry) => {
+                    self.read_counts[self.depth - 1] += 1;
+                    entry
+                }
+                Err(crate::Error::EndOfFile) if self.depth == 1 => match self.next_pass() {
+                    Ok(true) => continue,
+                    Ok(false) => {
+                        self.close_level();
+                        return None;
+                    }
+                    Err(e) => {
+                        self.close_level();
+                        return Some(Err(e));
+                    }
+                },
+                Err(crate::Error::EndOfFile) => {
+                    self.close_level();
+                    continue;
+                }
+                Err(e) => {
+                    self.close_level();
+                    return Some(Err(e));
+                }
+            };
+            if entry.is_dot_entry() {
+                continue;
+            }
+            let depth = self.depth;
+            let is_dir = entry.properties.is_dir();
+            let mut path = self.path.clone();
+            if let Err(e) = push_name(&mut path, entry.name_str(), is_dir) {
+                return Some(Err(e));
+            }
+            let item = WalkEntry {
+                path,
+                stat: entry.properties,
+                depth,
+            };
+            if self.prune.is_some_and(|prune| prune(&item)) {
+                continue;
+            }
+            let go_deeper = is_dir && depth < self.max_depth;
+            match self.order {
+                WalkOrder::DepthFirst => {
+                    if go_deeper {
+                        self.descend = Some(entry.name);
+                    }
+                    return Some(Ok(item));
+                }
+                WalkOrder::BreadthFirst if depth < self.pass_depth => {
+                    if go_deeper {
+                        self.descend = Some(entry.name);
+                    }
+                }
+                WalkOrder::BreadthFirst => {
+                    self.found_more |= go_deeper;
+                    return Some(Ok(item));
+                }
+            }
+        }
+    }
+}
+
+impl<'a, const N: usize> Drop for Walker<'a, N> {
+    fn drop(&mut self) {
+        while self.depth > 0 {
+            self.close_level();
+        }
+    }
+}
+
+/// A file or directory found by a [`Walker`].
+#[derive(Clone, Debug)]
+pub struct WalkEntry<const N: usize> {
+    /// The absolute path of the entry.
+    ///
+    /// Directories have a trailing `/`.
+    pub path: PathBuf<N>,
+    /// The properties of the entry.
+    pub stat: crate::file::Stat,
+    /// How far below the starting directory the entry is.
+    ///
+    /// Entries in the starting directory have a depth of one.
+    pub depth: usize,
+}
+
 // ============================================================================
 // Functions
 // ============================================================================
 
-// None
+/// Sort a list of directory entries.
+///
+/// The `.` and `..` entries always come first. Entries which are equal by
+/// the given key are sorted by name. To get the largest or newest entries
+/// first, reverse the slice afterwards.
+///
+/// This does not allocate, so it works on a `no_std` system.
+pub fn sort_entries(entries: &mut [Entry], key: SortKey) {
+    entries.sort_unstable_by(|a, b| {
+        b.is_dot_entry()
+            .cmp(&a.is_dot_entry())
+            .then_with(|| match key {
+                SortKey::Name => core::cmp::Ordering::Equal,
+                SortKey::Size => a.properties.file_size.cmp(&b.properties.file_size),
+                SortKey::Modified => a.properties.mtime.cmp(&b.properties.mtime),
+            })
+            .then_with(|| compare_names(a.basename(), b.basename()))
+            .then_with(|| compare_names(a.extension().unwrap_or(""), b.extension().unwrap_or("")))
+    });
+}
+
+/// Compare two names, ignoring ASCII case.
+fn compare_names(a: &str, b: &str) -> core::cmp::Ordering {
+    let a = a.bytes().map(|b| b.to_ascii_uppercase());
+    let b = b.bytes().map(|b| b.to_ascii_uppercase());
+    a.cmp(b)
+}
+
+/// Add a name from a directory entry to the end of a path.
+///
+/// Directories get a trailing `/`.
+fn push_name<const N: usize>(
+    path: &mut PathBuf<N>,
+    name: &str,
+    is_dir: bool,
+) -> Result<(), crate::Error> {
+    let mut buffer = [0u8; crate::MAX_FILENAME_LEN + 1];
+    let bytes = buffer
+        .get_mut(..name.len() + 1)
+        .ok_or(crate::Error::InvalidPath)?;
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    let len = if is_dir {
+        bytes[name.len()] = b'/';
+        name.len() + 1
+    } else {
+        name.len()
+    };
+    let name = core::str::from_utf8(&buffer[..len]).map_err(|_| crate::Error::InvalidPath)?;
+    path.push(name)
+}
+
+/// Check an 8.3 filename and convert it to the padded form used in
+/// [`Entry::name`].
+pub(crate) fn encode_name(name: &str) -> Result<[u8; crate::MAX_FILENAME_LEN], crate::Error> {
+    let mut result = [0u8; crate::MAX_FILENAME_LEN];
+    if name != Entry::DOT && name != Entry::DOT_DOT {
+        let (basename, extension) = match name.split_once('.') {
+            Some((basename, extension)) => (basename, Some(extension)),
+            None => (name, None),
+        };
+        if basename.is_empty() || basename.len() > 8 {
+            return Err(crate::Error::InvalidPath);
+        }
+        if let Some(extension) = extension {
+            if extension.is_empty() || extension.len() > 3 {
+                return Err(crate::Error::InvalidPath);
+            }
+        }
+        let all_chars = basename.bytes().chain(extension.unwrap_or("").bytes());
+        for byte in all_chars {
+            if !crate::path::is_short_name_char(byte) {
+                return Err(crate::Error::InvalidPath);
+            }
+        }
+    }
+    for (slot, byte) in result.iter_mut().zip(name.bytes()) {
+        *slot = byte.to_ascii_uppercase();
+    }
+    Ok(result)
+}
+
+/// Get the name from the padded form used in [`Entry::name`].
+///
+/// Trailing NUL bytes are padding. We also accept trailing spaces, as used on
+/// disk by FAT.
+pub(crate) fn decode_name(name: &[u8]) -> &str {
+    let len = name
+        .iter()
+        .rposition(|&b| b != 0 && b != b' ')
+        .map_or(0, |idx| idx + 1);
+    let name = &name[..len];
+    match core::str::from_utf8(name) {
+        Ok(s) => s,
+        Err(e) => {
+            // The bytes before the error are valid UTF-8
+            core::str::from_utf8(&name[..e.valid_up_to()]).unwrap_or("")
+        }
+    }
+}
 
 // ============================================================================
 // Tests
 // ============================================================================
 
-// None
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::file::{Stat, Time};
+    use crate::testing;
+    use std::{string::String, vec::Vec};
+
+    fn stat() -> Stat {
+        let time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        Stat {
+            file_size: 0,
+            ctime: time,
+            mtime: time,
+            attr: Attributes::empty(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn full_name() {
+        let entry = Entry::new("MYDOCU~1.TXT", stat()).unwrap();
+        assert_eq!(&entry.name, b"MYDOCU~1.TXT");
+        assert_eq!(entry.name_str(), "MYDOCU~1.TXT");
+        assert_eq!(entry.basename(), "MYDOCU~1");
+        assert_eq!(entry.extension(), Some("TXT"));
+    }
+
+    #[test]
+    fn short_name() {
+        let entry = Entry::new("A.C", stat()).unwrap();
+        assert_eq!(&entry.name, b"A.C\0\0\0\0\0\0\0\0\0");
+        assert_eq!(entry.name_str(), "A.C");
+        assert_eq!(entry.basename(), "A");
+        assert_eq!(entry.extension(), Some("C"));
+    }
+
+    #[test]
+    fn no_extension() {
+        let entry = Entry::new("BOOTLDR", stat()).unwrap();
+        assert_eq!(entry.name_str(), "BOOTLDR");
+        assert_eq!(entry.basename(), "BOOTLDR");
+        assert_eq!(entry.extension(), None);
+    }
+
+    #[test]
+    fn dot_entries() {
+        let entry = Entry::new(".", stat()).unwrap();
+        assert_eq!(entry.name_str(), ".");
+        assert_eq!(entry.basename(), ".");
+        assert_eq!(entry.extension(), None);
+        assert!(entry.is_dot_entry());
+        let entry = Entry::new("..", stat()).unwrap();
+        assert_eq!(entry.basename(), "..");
+        assert!(entry.is_dot_entry());
+        let entry = Entry::new("X", stat()).unwrap();
+        assert!(!entry.is_dot_entry());
+    }
+
+    #[test]
+    fn padding() {
+        let mut entry = Entry::new("X", stat()).unwrap();
+        entry.name = *b"README  .TXT";
+        // Spaces in the middle are not padding
+        assert_eq!(entry.name_str(), "README  .TXT");
+        entry.name = *b"README.TX   ";
+        assert_eq!(entry.name_str(), "README.TX");
+        assert_eq!(entry.extension(), Some("TX"));
+        entry.name = [0u8; crate::MAX_FILENAME_LEN];
+        assert_eq!(entry.name_str(), "");
+        entry.name = *b"BAD\xFFNAME\0\0\0\0";
+        assert_eq!(entry.name_str(), "BAD");
+    }
+
+    fn glob(pattern: &str) -> Result<Vec<String>, crate::Error> {
+        let api = testing::api();
+        let mut result = Vec::new();
+        for item in Glob::<64>::new(&api, pattern)? {
+            result.push(String::from(item?.path.as_str()));
+        }
+        assert_eq!(testing::open_handles(), 0);
+        Ok(result)
+    }
+
+    #[test]
+    fn entry_buffer() {
+        testing::reset(&["HD0:/DOCS/A.TXT", "HD0:/DOCS/B.TXT", "HD0:/DOCS/C.TXT"]);
+        let api = testing::api();
+        let handle = Result::from((api.opendir)("HD0:/DOCS".into())).unwrap();
+        let mut entries: [Entry; 3] = Default::default();
+        let count = Result::from((api.readdir_many)(handle, entries.as_mut_slice().into()));
+        assert_eq!(count, Ok(3));
+        let names: Vec<&str> = entries.iter().map(|e| e.name_str()).collect();
+        assert_eq!(names, [".", "..", "A.TXT"]);
+        let count = Result::from((api.readdir_many)(handle, entries.as_mut_slice().into()));
+        assert_eq!(count, Ok(2));
+        assert_eq!(entries[1].name_str(), "C.TXT");
+        let count = Result::from((api.readdir_many)(handle, entries.as_mut_slice().into()));
+        assert_eq!(count, Err(crate::Error::EndOfFile));
+        Result::from((api.closedir)(handle)).unwrap();
+    }
+
+    fn entry(name: &str, file_size: u64, day: u8) -> Entry {
+        let time = Time::new(2023, 6, day, 12, 0, 0).unwrap();
+        Entry::new(
+            name,
+            Stat {
+                file_size,
+                ctime: time,
+                mtime: time,
+                attr: Attributes::empty(),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name_str()).collect()
+    }
+
+    #[test]
+    fn sort_by_name() {
+        // `Entry::new` upper-cases, but an OS might still hand us lower-case
+        let mut lower = entry("B.TXT", 1, 1);
+        lower.name = *b"b.txt\0\0\0\0\0\0\0";
+        let mut entries = [
+            lower,
+            entry("A-B", 2, 2),
+            entry("..", 0, 3),
+            entry("A.TXT", 3, 3),
+            entry("A.DOC", 4, 4),
+            entry(".", 0, 5),
+        ];
+        sort_entries(&mut entries, SortKey::Name);
+        assert_eq!(
+            names(&entries),
+            [".", "..", "A.DOC", "A.TXT", "A-B", "b.txt"]
+        );
+    }
+
+    #[test]
+    fn sort_by_size_and_time() {
+        let mut entries = [
+            entry("C", 10, 1),
+            entry("A", 30, 2),
+            entry("..", 0, 9),
+            entry("B", 10, 3),
+        ];
+        sort_entries(&mut entries, SortKey::Size);
+        assert_eq!(names(&entries), ["..", "B", "C", "A"]);
+        sort_entries(&mut entries, SortKey::Modified);
+        assert_eq!(names(&entries), ["..", "C", "A", "B"]);
+    }
+
+    #[test]
+    fn read_dir_filter() {
+        testing::reset(&[
+            "HD0:/DOCS/A.TXT",
+            "HD0:/DOCS/B.TXT",
+            "HD0:/DOCS/C.DOC",
+            "HD0:/DOCS/SUB/",
+        ]);
+        let api = testing::api();
+        Result::from((api.set_attributes)(
+            "HD0:/DOCS/B.TXT".into(),
+            Attributes::HIDDEN,
+        ))
+        .unwrap();
+        let read = |filter| -> Vec<String> {
+            ReadDir::new(&api, "HD0:/DOCS/", filter)
+                .unwrap()
+                .map(|e| String::from(e.unwrap().name_str()))
+                .collect()
+        };
+        assert_eq!(read(Filter::new("*.TXT")), ["A.TXT"]);
+        assert_eq!(read(Filter::new("*")), [".", "..", "A.TXT", "C.DOC", "SUB"]);
+        assert_eq!(
+            read(Filter::all()),
+            [".", "..", "A.TXT", "B.TXT", "C.DOC", "SUB"]
+        );
+        let filter = Filter {
+            exclude: Attributes::DIRECTORY,
+            ..Filter::all()
+        };
+        assert_eq!(read(filter), ["A.TXT", "B.TXT", "C.DOC"]);
+        let filter = Filter {
+            dot_entries: false,
+            ..Filter::new("*.*")
+        };
+        assert_eq!(read(filter), ["A.TXT", "C.DOC", "SUB"]);
+        assert_eq!(testing::open_handles(), 0);
+    }
+
+    #[test]
+    fn read_dir_into_slice() {
+        testing::reset(&["HD0:/A.TXT", "HD0:/B.TXT", "HD0:/C.TXT"]);
+        let api = testing::api();
+        let mut read_dir = ReadDir::new(&api, "HD0:/", Filter::new("*.TXT")).unwrap();
+        let mut entries: [Entry; 2] = Default::default();
+        assert_eq!(read_dir.read_into(&mut entries), Ok(2));
+        assert_eq!(names(&entries), ["A.TXT", "B.TXT"]);
+        assert_eq!(read_dir.read_into(&mut entries), Ok(1));
+        assert_eq!(entries[0].name_str(), "C.TXT");
+        assert_eq!(read_dir.read_into(&mut entries), Ok(0));
+        read_dir.rewind().unwrap();
+        assert_eq!(read_dir.read_into(&mut entries), Ok(2));
+        assert_eq!(names(&entries), ["A.TXT", "B.TXT"]);
+    }
+
+    fn walk_fs() {
+        testing::reset(&[
+            "HD0:/TOP.TXT",
+            "HD0:/A/A1.TXT",
+            "HD0:/A/AA/AA1.TXT",
+            "HD0:/A/AA/AAA/AAA1.TXT",
+            "HD0:/A/A2.TXT",
+            "HD0:/B/B1.TXT",
+            "HD0:/C/",
+        ]);
+    }
+
+    fn walk(walker: Walker<64>) -> Vec<String> {
+        let mut result = Vec::new();
+        for item in walker {
+            assert!(testing::open_handles() <= 2);
+            let item = item.unwrap();
+            result.push(String::from(item.path.as_str()));
+        }
+        assert_eq!(testing::open_handles(), 0);
+        result
+    }
+
+    #[test]
+    fn walk_depth_first() {
+        walk_fs();
+        let api = testing::api();
+        for handles in [1, 2] {
+            let walker = Walker::new(&api, "HD0:/", WalkOrder::DepthFirst)
+                .unwrap()
+                .max_open_handles(handles);
+            assert_eq!(
+                walk(walker),
+                [
+                    "HD0:/A/",
+                    "HD0:/A/A1.TXT",
+                    "HD0:/A/A2.TXT",
+                    "HD0:/A/AA/",
+                    "HD0:/A/AA/AA1.TXT",
+                    "HD0:/A/AA/AAA/",
+                    "HD0:/A/AA/AAA/AAA1.TXT",
+                    "HD0:/B/",
+                    "HD0:/B/B1.TXT",
+                    "HD0:/C/",
+                    "HD0:/TOP.TXT",
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn walk_depths() {
+        walk_fs();
+        let api = testing::api();
+        let walker: Walker<64> = Walker::new(&api, "HD0:/A/AA", WalkOrder::DepthFirst).unwrap();
+        let depths: Vec<(String, usize)> = walker
+            .map(|item| item.unwrap())
+            .map(|item| (String::from(item.path.as_str()), item.depth))
+            .collect();
+        assert_eq!(
+            depths,
+            [
+                (String::from("HD0:/A/AA/AA1.TXT"), 1),
+                (String::from("HD0:/A/AA/AAA/"), 1),
+                (String::from("HD0:/A/AA/AAA/AAA1.TXT"), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_breadth_first() {
+        walk_fs();
+        let api = testing::api();
+        for handles in [1, 2] {
+            let walker = Walker::new(&api, "HD0:/", WalkOrder::BreadthFirst)
+                .unwrap()
+                .max_open_handles(handles);
+            assert_eq!(
+                walk(walker),
+                [
+                    "HD0:/A/",
+                    "HD0:/B/",
+                    "HD0:/C/",
+                    "HD0:/TOP.TXT",
+                    "HD0:/A/A1.TXT",
+                    "HD0:/A/A2.TXT",
+                    "HD0:/A/AA/",
+                    "HD0:/B/B1.TXT",
+                    "HD0:/A/AA/AA1.TXT",
+                    "HD0:/A/AA/AAA/",
+                    "HD0:/A/AA/AAA/AAA1.TXT",
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn walk_prune_and_limit() {
+        walk_fs();
+        let api = testing::api();
+        let not_aa = |item: &WalkEntry<64>| item.path.as_str().ends_with("/AA/");
+        let walker = Walker::new(&api, "HD0:/A", WalkOrder::DepthFirst)
+            .unwrap()
+            .prune(&not_aa);
+        assert_eq!(walk(walker), ["HD0:/A/A1.TXT", "HD0:/A/A2.TXT"]);
+        for order in [WalkOrder::DepthFirst, WalkOrder::BreadthFirst] {
+            let walker = Walker::new(&api, "HD0:/A/", order)
+                .unwrap()
+                .max_depth(2)
+                .max_open_handles(2);
+            let mut paths = walk(walker);
+            paths.sort();
+            assert_eq!(
+                paths,
+                [
+                    "HD0:/A/A1.TXT",
+                    "HD0:/A/A2.TXT",
+                    "HD0:/A/AA/",
+                    "HD0:/A/AA/AA1.TXT",
+                    "HD0:/A/AA/AAA/",
+                ]
+            );
+        }
+    }
+
+    fn glob_fs() {
+        testing::reset(&[
+            "HD0:/TOP.TXT",
+            "HD0:/DOCS/SALES.TXT",
+            "HD0:/DOCS/REPORT01.DOC",
+            "HD0:/DOCS/REPORT1.DOC",
+            "HD0:/DOCS/OLD/REPORT02.DOC",
+            "HD0:/MISC/REPORT03.DOC",
+            "HD0:/MISC/README",
+            "HD0:/EMPTY/",
+        ]);
+    }
+
+    #[test]
+    fn glob_filename() {
+        glob_fs();
+        assert_eq!(glob("HD0:/DOCS/*.TXT").unwrap(), ["HD0:/DOCS/SALES.TXT"]);
+        assert_eq!(
+            glob("HD0:/DOCS/*").unwrap(),
+            [
+                "HD0:/DOCS/OLD/",
+                "HD0:/DOCS/REPORT01.DOC",
+                "HD0:/DOCS/REPORT1.DOC",
+                "HD0:/DOCS/SALES.TXT"
+            ]
+        );
+        assert_eq!(glob("HD0:/MISC/*.*").unwrap().len(), 2);
+        assert_eq!(
+            glob("HD0:/DOCS/sales.txt").unwrap(),
+            ["HD0:/DOCS/SALES.TXT"]
+        );
+        assert!(glob("HD0:/EMPTY/*").unwrap().is_empty());
+    }
+
+    #[test]
+    fn glob_directories() {
+        glob_fs();
+        assert_eq!(
+            glob("HD0:/*/REPORT??.DOC").unwrap(),
+            ["HD0:/DOCS/REPORT01.DOC", "HD0:/MISC/REPORT03.DOC"]
+        );
+        assert_eq!(
+            glob("HD0:/*/*/REPORT??.DOC").unwrap(),
+            ["HD0:/DOCS/OLD/REPORT02.DOC"]
+        );
+        assert_eq!(
+            glob("HD0:/*/").unwrap(),
+            ["HD0:/DOCS/", "HD0:/EMPTY/", "HD0:/MISC/"]
+        );
+    }
+
+    #[test]
+    fn glob_relative() {
+        glob_fs();
+        assert_eq!(glob("*.TXT").unwrap(), ["HD0:/TOP.TXT"]);
+        assert_eq!(glob("DOCS/R*T1.DOC").unwrap(), ["HD0:/DOCS/REPORT1.DOC"]);
+    }
+
+    #[test]
+    fn glob_errors() {
+        glob_fs();
+        assert_eq!(glob("HD0:/NOPE/*"), Err(crate::Error::NotFound));
+        assert_eq!(glob("HD0:/"), Err(crate::Error::InvalidPath));
+        assert_eq!(glob("HD0:/*/../*"), Err(crate::Error::InvalidPath));
+        assert_eq!(glob("*/*/*/*/*/*/*/*/*"), Err(crate::Error::InvalidArg));
+    }
+
+    #[test]
+    fn invalid_names() {
+        for name in [
+            "",
+            ".TXT",
+            "README.",
+            "TOOLONGNA.TXT",
+            "README.TEXT",
+            "A.B.C",
+            "SALES*.TXT",
+            "MY FILE.TXT",
+            "CAF\u{e9}.TXT",
+            "...",
+        ] {
+            assert_eq!(
+                Entry::new(name, stat()),
+                Err(crate::Error::InvalidPath),
+                "{name:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn mkdir() {
+        testing::reset(&["HD0:/DOCS/A.TXT"]);
+        let api = testing::api();
+        let mkdir = |path: &str| Result::from((api.mkdir)(path.into()));
+        assert_eq!(mkdir("HD0:/DOCS/NEW"), Ok(()));
+        Result::from((api.chdir)("HD0:/DOCS".into())).unwrap();
+        assert_eq!(mkdir("NEW/SUB/"), Ok(()));
+        let stat = Result::from((api.stat)("HD0:/DOCS/NEW/SUB".into())).unwrap();
+        assert!(stat.is_dir());
+        assert_eq!(stat.attr, Attributes::DIRECTORY);
+        assert_eq!(mkdir("NEW"), Err(crate::Error::AlreadyExists));
+        assert_eq!(mkdir("A.TXT"), Err(crate::Error::AlreadyExists));
+        assert_eq!(mkdir("HD0:/NOPE/NEW"), Err(crate::Error::NotFound));
+        assert_eq!(mkdir("A.TXT/NEW"), Err(crate::Error::NotFound));
+        let names: Vec<String> = ReadDir::new(&api, "HD0:/DOCS/NEW", Filter::all())
+            .unwrap()
+            .map(|e| String::from(e.unwrap().name_str()))
+            .collect();
+        assert_eq!(names, [".", "..", "SUB"]);
+    }
+
+    #[test]
+    fn lower_case_names() {
+        let entry = Entry::new("readme.txt", stat()).unwrap();
+        assert_eq!(&entry.name, b"README.TXT\0\0");
+        assert_eq!(entry.name_str(), "README.TXT");
+        let entry = Entry::new("MyDocu~1.Txt", stat()).unwrap();
+        assert_eq!(entry.name_str(), "MYDOCU~1.TXT");
+    }
+
+    #[test]
+    fn tell_seek_rewind() {
+        testing::reset(&["HD0:/A.TXT", "HD0:/B.TXT", "HD0:/C.TXT", "HD0:/D.TXT"]);
+        let api = testing::api();
+        let handle = Result::from((api.opendir)("HD0:/".into())).unwrap();
+        let read_name =
+            || Result::from((api.readdir)(handle)).map(|entry| String::from(entry.name_str()));
+        assert_eq!(read_name().unwrap(), "A.TXT");
+        let start = Result::from((api.telldir)(handle)).unwrap();
+        assert_eq!(read_name().unwrap(), "B.TXT");
+        let middle = Result::from((api.telldir)(handle)).unwrap();
+        assert_eq!(read_name().unwrap(), "C.TXT");
+        assert_eq!(read_name().unwrap(), "D.TXT");
+        let end = Result::from((api.telldir)(handle)).unwrap();
+        assert_eq!(read_name(), Err(crate::Error::EndOfFile));
+        // Go back, then forwards again
+        Result::from((api.seekdir)(handle, middle)).unwrap();
+        assert_eq!(read_name().unwrap(), "C.TXT");
+        Result::from((api.seekdir)(handle, start)).unwrap();
+        assert_eq!(read_name().unwrap(), "B.TXT");
+        Result::from((api.seekdir)(handle, end)).unwrap();
+        assert_eq!(read_name(), Err(crate::Error::EndOfFile));
+        Result::from((api.rewinddir)(handle)).unwrap();
+        assert_eq!(read_name().unwrap(), "A.TXT");
+        assert_eq!(Result::from((api.telldir)(handle)), Ok(start));
+        Result::from((api.closedir)(handle)).unwrap();
+        assert_eq!(
+            Result::from((api.telldir)(handle)),
+            Err(crate::Error::BadHandle)
+        );
+        assert_eq!(
+            Result::from((api.seekdir)(handle, start)),
+            Err(crate::Error::BadHandle)
+        );
+        assert_eq!(
+            Result::from((api.rewinddir)(handle)),
+            Err(crate::Error::BadHandle)
+        );
+    }
+}
 
 // ============================================================================
 // End of File
@@ -0,0 +1,392 @@
+//! Drive related types
+
+// ============================================================================
+// Imports
+// ============================================================================
+
+use crate::{Api, Error, FfiBuffer};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+// None
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// The kinds of filesystem a drive can hold.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FsType {
+    /// The filesystem was not recognised.
+    ///
+    /// This is only ever reported. To have [`Api::mount`] work out the
+    /// filesystem, pass [`FsType::Auto`].
+    Unknown,
+    /// A drive containing devices rather than files, like `DEV:`
+    Device,
+    /// The FAT12 filesystem, as used on floppy disks
+    Fat12,
+    /// The FAT16 filesystem
+    Fat16,
+    /// The FAT32 filesystem
+    Fat32,
+    /// The exFAT filesystem
+    ExFat,
+    /// Ask [`Api::mount`] to work out the filesystem from the volume.
+    ///
+    /// This is never reported for a mounted drive.
+    Auto,
+}
+
+/// Describes the volume mounted on a drive.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeInfo {
+    /// The kind of filesystem on the volume
+    pub fs_type: FsType,
+    /// The total size of the volume, in bytes
+    pub total_bytes: u64,
+    /// How much space is free on the volume, in bytes
+    pub free_bytes: u64,
+    /// The size of an allocation unit (cluster), in bytes.
+    ///
+    /// Every file takes up a whole number of clusters on disk.
+    pub cluster_size: u32,
+    /// The volume label.
+    ///
+    /// The label is left-aligned in the array, and any unused bytes at the end
+    /// are set to zero. FAT stores labels padded with spaces, so the OS must
+    /// replace that padding with zeros. Use [`VolumeInfo::label_str`] rather
+    /// than reading this field directly.
+    pub label: [u8; crate::MAX_VOLUME_LABEL_LEN],
+    /// The volume serial number, or zero if the volume doesn't have one
+    pub serial_number: u32,
+}
+
+impl VolumeInfo {
+    /// Get the volume label, with any padding removed.
+    ///
+    /// Volumes without a label give an empty string.
+    pub fn label_str(&self) -> &str {
+        crate::dir::decode_name(&self.label)
+    }
+}
+
+/// The name of a drive, as returned by [`Drives`].
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct DriveName([u8; crate::MAX_DRIVE_NAME_LEN]);
+
+impl DriveName {
+    /// Get the drive name (e.g. `HD0`), without the trailing `:`.
+    pub fn as_str(&self) -> &str {
+        crate::dir::decode_name(&self.0)
+    }
+}
+
+impl core::fmt::Debug for DriveName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "DriveName({:?})", self.as_str())
+    }
+}
+
+impl core::fmt::Display for DriveName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Lists every drive, using [`Api::list_drives`].
+///
+/// ```no_run
+/// # use neotron_api::{Api, drive::Drives};
+/// # fn example(api: &Api) -> Result<(), neotron_api::Error> {
+/// for drive in Drives::new(api) {
+///     let drive = drive?;
+///     let info = Result::from((api.volume_info)(drive.as_str().into()));
+///     // ... process drive ...
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Drives<'a> {
+    api: &'a Api,
+    /// The index of the next drive to ask for
+    index: usize,
+    /// Have we reached the end (or hit an error)?
+    finished: bool,
+}
+
+impl<'a> Drives<'a> {
+    /// Start listing the drives, from the first one.
+    pub fn new(api: &'a Api) -> Drives<'a> {
+        Drives {
+            api,
+            index: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Drives<'a> {
+    type Item = Result<DriveName, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut name = [0u8; crate::MAX_DRIVE_NAME_LEN];
+        let result = (self.api.list_drives)(self.index, FfiBuffer::new(&mut name));
+        match Result::from(result) {
+            Ok(len) => {
+                self.index += 1;
+                // The name is zero-padded, so nothing past `len` may be set
+                name.get_mut(len..).unwrap_or_default().fill(0);
+                Some(Ok(DriveName(name)))
+            }
+            Err(Error::EndOfFile) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Functions
+// ============================================================================
+
+// None
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{string::String, vec::Vec};
+
+    use super::*;
+    use crate::testing;
+
+    fn list_drive(index: usize, buffer: &mut [u8]) -> Result<usize, Error> {
+        let api = testing::api();
+        Result::from((api.list_drives)(index, FfiBuffer::new(buffer)))
+    }
+
+    #[test]
+    fn list_drives() {
+        testing::reset(&["HD1:/DOCS/A.TXT", "HD0:/", "FLOPPY0:/"]);
+        let mut buffer = [0u8; crate::MAX_DRIVE_NAME_LEN];
+        assert_eq!(list_drive(0, &mut buffer), Ok(7));
+        assert_eq!(&buffer[..7], b"FLOPPY0");
+        assert_eq!(list_drive(2, &mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], b"HD1");
+        assert_eq!(list_drive(3, &mut buffer), Err(Error::EndOfFile));
+        assert_eq!(list_drive(usize::MAX, &mut buffer), Err(Error::EndOfFile));
+        // Too small for `FLOPPY0`, but big enough for `HD0`
+        let mut small = [0u8; 3];
+        assert_eq!(list_drive(0, &mut small), Err(Error::InvalidArg));
+        assert_eq!(list_drive(1, &mut small), Ok(3));
+    }
+
+    #[test]
+    fn drives_iterator() {
+        testing::reset(&["HD1:/", "HD0:/", "FLOPPY0:/"]);
+        let api = testing::api();
+        let mut drives = Drives::new(&api);
+        let names: Vec<String> = drives
+            .by_ref()
+            .map(|d| String::from(d.unwrap().as_str()))
+            .collect();
+        assert_eq!(names, ["FLOPPY0", "HD0", "HD1"]);
+        assert!(drives.next().is_none());
+        testing::reset(&[]);
+        assert_eq!(Drives::new(&api).count(), 0);
+    }
+
+    #[test]
+    fn volume_info() {
+        testing::reset(&["HD0:/A.TXT", "FLOPPY0:/"]);
+        let api = testing::api();
+        let info = Result::from((api.volume_info)("hd0:".into())).unwrap();
+        assert_eq!(info.fs_type, FsType::Fat32);
+        assert_eq!(&info.label, b"HD0\0\0\0\0\0\0\0\0");
+        assert_eq!(info.label_str(), "HD0");
+        let info = Result::from((api.volume_info)("FLOPPY0".into())).unwrap();
+        assert_eq!(info.label_str(), "FLOPPY0");
+        assert_eq!(
+            Result::from((api.volume_info)("HD9:".into())),
+            Err(Error::NotFound)
+        );
+    }
+
+    #[test]
+    fn label_str() {
+        let mut info = VolumeInfo {
+            fs_type: FsType::Fat16,
+            total_bytes: 0,
+            free_bytes: 0,
+            cluster_size: 512,
+            label: *b"NO NAME    ",
+            serial_number: 0,
+        };
+        // Spaces in the middle are part of the label
+        assert_eq!(info.label_str(), "NO NAME");
+        info.label = *b"BOOT\0\0\0\0\0\0\0";
+        assert_eq!(info.label_str(), "BOOT");
+        info.label = [0u8; crate::MAX_VOLUME_LABEL_LEN];
+        assert_eq!(info.label_str(), "");
+        info.label = *b"           ";
+        assert_eq!(info.label_str(), "");
+    }
+
+    fn mount(device: &str, drive: &str, fs_type: FsType) -> Result<(), Error> {
+        let api = testing::api();
+        Result::from((api.mount)(device.into(), drive.into(), fs_type))
+    }
+
+    fn unmount(drive: &str) -> Result<(), Error> {
+        let api = testing::api();
+        Result::from((api.unmount)(drive.into()))
+    }
+
+    fn assign(alias: &str, target: &str) -> Result<(), Error> {
+        let api = testing::api();
+        Result::from((api.assign)(alias.into(), target.into()))
+    }
+
+    fn unassign(alias: &str) -> Result<(), Error> {
+        let api = testing::api();
+        Result::from((api.unassign)(alias.into()))
+    }
+
+    #[test]
+    fn mount_and_unmount() {
+        testing::reset(&["HD0:/DISK.IMG", "HD0:/DOCS/"]);
+        let api = testing::api();
+        assert_eq!(mount("SDCARD0", "sd0:", FsType::Auto), Ok(()));
+        assert_eq!(mount("HD0:/DISK.IMG", "IMG", FsType::Fat16), Ok(()));
+        let names: Vec<String> = Drives::new(&api)
+            .map(|d| String::from(d.unwrap().as_str()))
+            .collect();
+        assert_eq!(names, ["HD0", "IMG", "SD0"]);
+        // Auto is never reported; we get what the OS found
+        let info = Result::from((api.volume_info)("SD0".into())).unwrap();
+        assert_eq!(info.fs_type, FsType::Fat32);
+        let info = Result::from((api.volume_info)("IMG".into())).unwrap();
+        assert_eq!(info.fs_type, FsType::Fat16);
+        // The new drive works with paths
+        assert_eq!(Result::from((api.mkdir)("SD0:/MUSIC".into())), Ok(()));
+        assert!(testing::paths().contains(&String::from("SD0:/MUSIC")));
+
+        assert_eq!(unmount("SD0:"), Ok(()));
+        assert_eq!(unmount("img"), Ok(()));
+        assert_eq!(testing::paths(), ["HD0:", "HD0:/DISK.IMG", "HD0:/DOCS"]);
+        assert_eq!(unmount("SD0"), Err(Error::NotFound));
+        // The name can be used again
+        assert_eq!(mount("SDCARD1", "SD0", FsType::Fat32), Ok(()));
+        assert_eq!(unmount("SD0"), Ok(()));
+    }
+
+    #[test]
+    fn mount_errors() {
+        testing::reset(&["HD0:/DISK.IMG", "HD0:/DOCS/"]);
+        // Only filesystems (or Auto) can be asked for
+        assert_eq!(
+            mount("SDCARD0", "SD0", FsType::Unknown),
+            Err(Error::InvalidArg)
+        );
+        assert_eq!(
+            mount("SDCARD0", "SD0", FsType::Device),
+            Err(Error::InvalidArg)
+        );
+        assert_eq!(mount("SDCARD9", "SD0", FsType::Auto), Err(Error::NotFound));
+        assert_eq!(
+            mount("HD0:/NONE.IMG", "SD0", FsType::Auto),
+            Err(Error::NotFound)
+        );
+        assert_eq!(
+            mount("SDCARD0", "hd0", FsType::Auto),
+            Err(Error::AlreadyExists)
+        );
+        assert_eq!(mount("SDCARD0", "", FsType::Auto), Err(Error::InvalidArg));
+        // An open disk image can't be mounted
+        let api = testing::api();
+        let flags = crate::file::Flags::empty();
+        let fd = Result::from((api.open)("HD0:/DISK.IMG".into(), flags)).unwrap();
+        assert_eq!(
+            mount("HD0:/DISK.IMG", "IMG", FsType::Auto),
+            Err(Error::InvalidArg)
+        );
+        // Nor can a drive with something open on it be unmounted
+        assert_eq!(unmount("HD0"), Err(Error::InvalidArg));
+        assert_eq!(Result::from((api.close)(fd)), Ok(()));
+        let dir = Result::from((api.opendir)("HD0:/DOCS".into())).unwrap();
+        assert_eq!(unmount("HD0"), Err(Error::InvalidArg));
+        assert_eq!(Result::from((api.closedir)(dir)), Ok(()));
+        assert_eq!(unmount("HD0"), Ok(()));
+        assert!(testing::paths().is_empty());
+    }
+
+    #[test]
+    fn assign_and_unassign() {
+        testing::reset(&["HD0:/NEOTRON/CONFIG.TXT", "HD0:/NEOTRON/BIN/", "HD1:/"]);
+        let api = testing::api();
+        assert_eq!(assign("SYS:", "HD0:/NEOTRON/"), Ok(()));
+        let stat = Result::from((api.stat)("sys:/config.txt".into())).unwrap();
+        assert!(!stat.attr.contains(crate::file::Attributes::DIRECTORY));
+        // Aliases work with every function that takes a path
+        assert_eq!(Result::from((api.mkdir)("SYS:/ETC".into())), Ok(()));
+        assert!(testing::paths().contains(&String::from("HD0:/NEOTRON/ETC")));
+        // An alias may refer to another alias
+        assert_eq!(assign("BIN", "SYS:/BIN"), Ok(()));
+        assert!(Result::from((api.stat)("BIN:".into())).is_ok());
+        // The target's drive can't be unmounted while the alias exists
+        assert_eq!(unmount("HD0"), Err(Error::InvalidArg));
+        // Assigning again changes the target
+        assert_eq!(assign("SYS", "HD1:/"), Ok(()));
+        assert_eq!(
+            Result::from((api.stat)("SYS:/CONFIG.TXT".into())),
+            Err(Error::NotFound)
+        );
+        assert_eq!(unassign("sys"), Ok(()));
+        assert_eq!(unassign("SYS"), Err(Error::NotFound));
+        assert_eq!(
+            Result::from((api.stat)("SYS:/CONFIG.TXT".into())),
+            Err(Error::NotFound)
+        );
+        assert_eq!(unassign("BIN:"), Ok(()));
+        assert_eq!(unmount("HD0"), Ok(()));
+    }
+
+    #[test]
+    fn assign_errors() {
+        testing::reset(&["HD0:/NEOTRON/CONFIG.TXT"]);
+        assert_eq!(assign("HD0", "HD0:/NEOTRON"), Err(Error::AlreadyExists));
+        assert_eq!(assign("SYS", "HD0:/NONE"), Err(Error::NotFound));
+        assert_eq!(
+            assign("SYS", "HD0:/NEOTRON/CONFIG.TXT"),
+            Err(Error::InvalidPath)
+        );
+        assert_eq!(assign("SYS", "NEOTRON"), Err(Error::InvalidPath));
+        assert_eq!(assign("SYS", "HD0:/NEOTRON"), Ok(()));
+        // A drive can't then be mounted with the alias's name
+        assert_eq!(
+            mount("SDCARD0", "SYS", FsType::Auto),
+            Err(Error::AlreadyExists)
+        );
+    }
+}
+
+// ============================================================================
+// End of File
+// ============================================================================
@@ -6,6 +6,8 @@
 
 use bitflags::bitflags;
 
+use crate::FfiOption;
+
 // ============================================================================
 // Constants
 // ============================================================================
@@ -65,7 +67,7 @@ impl Handle {
 ///
 /// This is set up for 8.3 filenames on MS-DOS FAT32 partitions currently.
 #[repr(C)]
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Stat {
     /// How big is this file
     pub file_size: u64,
@@ -75,6 +77,67 @@ pub struct Stat {
     pub mtime: Time,
     /// File attributes (Directory, Volume, etc)
     pub attr: Attributes,
+    /// When was the file last read or written.
+    ///
+    /// FAT only records the date, so the time will be midnight.
+    pub atime: Time,
+    /// How much space does this file take up on disk, in bytes.
+    ///
+    /// This is `file_size` rounded up to a whole number of clusters.
+    pub allocated_size: u64,
+    /// The name of the drive this file is on, without the trailing `:`.
+    ///
+    /// This is the real drive, even if the file was found through an alias
+    /// (see [`Api::assign`](crate::Api::assign)). The name is left-aligned,
+    /// and any unused bytes at the end are set to zero. Use
+    /// [`Stat::drive_name`] rather than reading this field directly.
+    pub drive: [u8; crate::MAX_DRIVE_NAME_LEN],
+}
+
+impl Stat {
+    /// Get the name of the drive this file is on (e.g. `HD0`).
+    pub fn drive_name(&self) -> &str {
+        crate::dir::decode_name(&self.drive)
+    }
+
+    /// Is this a directory?
+    pub fn is_dir(&self) -> bool {
+        self.attr.contains(Attributes::DIRECTORY)
+    }
+
+    /// Is this an ordinary file?
+    ///
+    /// That is, it is not a directory, a device or a volume label.
+    pub fn is_file(&self) -> bool {
+        !self
+            .attr
+            .intersects(Attributes::DIRECTORY | Attributes::DEVICE | Attributes::VOLUME)
+    }
+
+    /// Is this a device?
+    pub fn is_device(&self) -> bool {
+        self.attr.contains(Attributes::DEVICE)
+    }
+
+    /// Is this a volume label?
+    pub fn is_volume_label(&self) -> bool {
+        self.attr.contains(Attributes::VOLUME)
+    }
+
+    /// Is this read-only?
+    pub fn is_read_only(&self) -> bool {
+        self.attr.contains(Attributes::READ_ONLY)
+    }
+
+    /// Should this be left out of directory listings?
+    pub fn is_hidden(&self) -> bool {
+        self.attr.contains(Attributes::HIDDEN)
+    }
+
+    /// Is this a system file?
+    pub fn is_system(&self) -> bool {
+        self.attr.contains(Attributes::SYSTEM)
+    }
 }
 
 bitflags! {
@@ -88,12 +151,39 @@ bitflags! {
         const CREATE = 0x02;
         /// Truncate the file to zero length upon opening.
         const TRUNCATE = 0x04;
+        /// Create the file, failing with `Error::AlreadyExists` if it exists.
+        ///
+        /// Checking and creating happen as one step, so two applications
+        /// cannot both create the same file.
+        ///
+        /// This implies [`Flags::CREATE`], and takes precedence over it -
+        /// setting both is the same as setting only `CREATE_NEW`.
+        const CREATE_NEW = 0x08;
+        /// Delete the file when it is closed.
+        ///
+        /// If the file is also open on other handles, it is deleted when the
+        /// last of them is closed.
+        const DELETE_ON_CLOSE = 0x10;
     }
 }
 
 bitflags! {
     #[repr(C)]
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    /// Describes how to copy a file.
+    pub struct CopyFlags: u8 {
+        /// Replace the destination file if it exists.
+        const OVERWRITE = 0x01;
+        /// Give the destination file the same attributes as the source.
+        const PRESERVE_ATTRIBUTES = 0x02;
+        /// Give the destination file the same timestamps as the source.
+        const PRESERVE_TIMES = 0x04;
+    }
+}
+
+bitflags! {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
     /// The attributes a file on disk can have.alloc
     ///
     /// Based on that supported by the FAT32 file system.
@@ -116,8 +206,17 @@ bitflags! {
 }
 
 /// Represents an instant in time, in the local time zone.
+///
+/// See [`TimeZone`] for converting to and from UTC.
+///
+/// The fields are stored in a compact, FFI-safe form. Use [`Time::new`] to
+/// build a `Time` from calendar values, and the accessor methods (such as
+/// [`Time::year`] and [`Time::month`]) to get calendar values back out.
+///
+/// A `Time` can represent any second from `1970-01-01T00:00:00` to
+/// `2225-12-31T23:59:59`. Leap seconds are not supported.
 #[repr(C)]
-#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Default, Copy, Clone, PartialOrd, Ord, PartialEq, Eq)]
 pub struct Time {
     /// Add 1970 to this file to get the calendar year
     pub year_since_1970: u8,
@@ -133,17 +232,1190 @@ pub struct Time {
     pub seconds: u8,
 }
 
+impl Time {
+    /// The earliest calendar year a `Time` can represent.
+    pub const MIN_YEAR: u16 = 1970;
+
+    /// The latest calendar year a `Time` can represent.
+    pub const MAX_YEAR: u16 = Self::MIN_YEAR + u8::MAX as u16;
+
+    /// The earliest calendar year a FAT timestamp can represent.
+    pub const FAT_MIN_YEAR: u16 = 1980;
+
+    /// The latest calendar year a FAT timestamp can represent.
+    pub const FAT_MAX_YEAR: u16 = Self::FAT_MIN_YEAR + 127;
+
+    /// The number of days between 0000-03-01 and 1970-01-01 in the proleptic
+    /// Gregorian calendar.
+    const UNIX_EPOCH_DAYS: u32 = 719_468;
+
+    /// The number of seconds in a day
+    const SECONDS_PER_DAY: u64 = 86_400;
+
+    /// Construct a new `Time` from calendar values.
+    ///
+    /// * `year` is the calendar year (e.g. `2023`)
+    /// * `month` is the calendar month, from `1` (January) to `12` (December)
+    /// * `day` is the calendar day, from `1` to `31`
+    /// * `hours` is from `0` to `23`
+    /// * `minutes` and `seconds` are from `0` to `59`
+    ///
+    /// If the values do not describe a valid time (e.g. February 30th), you
+    /// get `Err(Error::InvalidArg)`.
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+    ) -> Result<Time, crate::Error> {
+        if !(Self::MIN_YEAR..=Self::MAX_YEAR).contains(&year) || month == 0 || day == 0 {
+            return Err(crate::Error::InvalidArg);
+        }
+        let time = Time {
+            year_since_1970: (year - Self::MIN_YEAR) as u8,
+            zero_indexed_month: month - 1,
+            zero_indexed_day: day - 1,
+            hours,
+            minutes,
+            seconds,
+        };
+        if time.is_valid() {
+            Ok(time)
+        } else {
+            Err(crate::Error::InvalidArg)
+        }
+    }
+
+    /// Does this `Time` describe a real calendar date and time?
+    ///
+    /// The fields of a `Time` are public, so it is possible to construct
+    /// nonsense like February 30th, or 25 o'clock. This function checks for
+    /// that.
+    pub fn is_valid(&self) -> bool {
+        if self.zero_indexed_month >= 12 {
+            return false;
+        }
+        let days = days_in_month(self.year(), self.zero_indexed_month + 1);
+        self.zero_indexed_day < days && self.hours < 24 && self.minutes < 60 && self.seconds < 60
+    }
+
+    /// Get the calendar year (e.g. `2023`).
+    pub const fn year(&self) -> u16 {
+        Self::MIN_YEAR + self.year_since_1970 as u16
+    }
+
+    /// Get the calendar month, where `1` is January.
+    ///
+    /// This is only meaningful if the `Time` is valid - see [`Time::is_valid`].
+    pub const fn month(&self) -> u8 {
+        self.zero_indexed_month.wrapping_add(1)
+    }
+
+    /// Get the calendar day of the month, where `1` is the first day.
+    ///
+    /// This is only meaningful if the `Time` is valid - see [`Time::is_valid`].
+    pub const fn day(&self) -> u8 {
+        self.zero_indexed_day.wrapping_add(1)
+    }
+
+    /// Get the day of the week this `Time` falls on.
+    ///
+    /// If the `Time` is not valid (see [`Time::is_valid`]), you get
+    /// `Err(Error::InvalidArg)`.
+    pub fn day_of_week(&self) -> Result<Weekday, crate::Error> {
+        Ok(Weekday::from_days_since_epoch(self.days_since_epoch()?))
+    }
+
+    /// Convert to the number of seconds since `1970-01-01T00:00:00`.
+    ///
+    /// If the `Time` is not valid (see [`Time::is_valid`]), you get
+    /// `Err(Error::InvalidArg)`.
+    pub fn to_unix_seconds(&self) -> Result<u64, crate::Error> {
+        Ok(u64::from(self.days_since_epoch()?) * Self::SECONDS_PER_DAY
+            + u64::from(self.hours) * 3600
+            + u64::from(self.minutes) * 60
+            + u64::from(self.seconds))
+    }
+
+    /// Convert from the number of seconds since `1970-01-01T00:00:00`.
+    ///
+    /// If the result would be after [`Time::MAX_YEAR`], you get
+    /// `Err(Error::InvalidArg)`.
+    pub fn from_unix_seconds(unix_seconds: u64) -> Result<Time, crate::Error> {
+        let days = unix_seconds / Self::SECONDS_PER_DAY;
+        let seconds_of_day = unix_seconds % Self::SECONDS_PER_DAY;
+        // The first day we can't represent
+        let end_days = days_from_civil(u32::from(Self::MAX_YEAR) + 1, 1, 1);
+        if days >= u64::from(end_days) {
+            return Err(crate::Error::InvalidArg);
+        }
+        let (year, month, day) = civil_from_days(days as u32);
+        Ok(Time {
+            year_since_1970: (year - u32::from(Self::MIN_YEAR)) as u8,
+            zero_indexed_month: month - 1,
+            zero_indexed_day: day - 1,
+            hours: (seconds_of_day / 3600) as u8,
+            minutes: ((seconds_of_day / 60) % 60) as u8,
+            seconds: (seconds_of_day % 60) as u8,
+        })
+    }
+
+    /// Convert to a packed FAT date word.
+    ///
+    /// Bits 15-9 are the year since 1980, bits 8-5 are the month (1-12) and
+    /// bits 4-0 are the day (1-31).
+    ///
+    /// If the `Time` is not valid (see [`Time::is_valid`]), or the year cannot
+    /// be represented (i.e. it is before [`Time::FAT_MIN_YEAR`] or after
+    /// [`Time::FAT_MAX_YEAR`]), you get `Err(Error::InvalidArg)`.
+    pub fn to_fat_date(&self) -> Result<u16, crate::Error> {
+        let year = self.year();
+        if !self.is_valid() || !(Self::FAT_MIN_YEAR..=Self::FAT_MAX_YEAR).contains(&year) {
+            return Err(crate::Error::InvalidArg);
+        }
+        Ok(((year - Self::FAT_MIN_YEAR) << 9)
+            | (u16::from(self.month()) << 5)
+            | u16::from(self.day()))
+    }
+
+    /// Convert to a packed FAT time word.
+    ///
+    /// Bits 15-11 are the hours, bits 10-5 are the minutes and bits 4-0 are
+    /// the seconds divided by two. FAT only has a two second resolution, so
+    /// odd seconds are rounded down.
+    ///
+    /// If the `Time` is not valid (see [`Time::is_valid`]), you get
+    /// `Err(Error::InvalidArg)`.
+    pub fn to_fat_time(&self) -> Result<u16, crate::Error> {
+        if !self.is_valid() {
+            return Err(crate::Error::InvalidArg);
+        }
+        Ok((u16::from(self.hours) << 11)
+            | (u16::from(self.minutes) << 5)
+            | u16::from(self.seconds / 2))
+    }
+
+    /// Convert from packed FAT date and time words.
+    ///
+    /// See [`Time::to_fat_date`] and [`Time::to_fat_time`] for the layout. If
+    /// the words do not describe a valid time, you get
+    /// `Err(Error::InvalidArg)`.
+    pub fn from_fat(date: u16, time: u16) -> Result<Time, crate::Error> {
+        Time::new(
+            Self::FAT_MIN_YEAR + (date >> 9),
+            ((date >> 5) & 0x0F) as u8,
+            (date & 0x1F) as u8,
+            (time >> 11) as u8,
+            ((time >> 5) & 0x3F) as u8,
+            ((time & 0x1F) * 2) as u8,
+        )
+    }
+
+    /// Add a duration to this `Time`.
+    ///
+    /// Any fractional seconds in `duration` are ignored. You get `None` if
+    /// this `Time` is not valid, or if the result cannot be represented.
+    pub fn checked_add(&self, duration: core::time::Duration) -> Option<Time> {
+        let unix_seconds = self
+            .to_unix_seconds()
+            .ok()?
+            .checked_add(duration.as_secs())?;
+        Time::from_unix_seconds(unix_seconds).ok()
+    }
+
+    /// Subtract a duration from this `Time`.
+    ///
+    /// Any fractional seconds in `duration` are ignored. You get `None` if
+    /// this `Time` is not valid, or if the result cannot be represented.
+    pub fn checked_sub(&self, duration: core::time::Duration) -> Option<Time> {
+        let unix_seconds = self
+            .to_unix_seconds()
+            .ok()?
+            .checked_sub(duration.as_secs())?;
+        Time::from_unix_seconds(unix_seconds).ok()
+    }
+
+    /// Get the amount of time that has passed between `earlier` and this `Time`.
+    ///
+    /// You get `None` if `earlier` is actually later than this `Time`, or if
+    /// either `Time` is not valid.
+    pub fn duration_since(&self, earlier: &Time) -> Option<core::time::Duration> {
+        let seconds = self
+            .to_unix_seconds()
+            .ok()?
+            .checked_sub(earlier.to_unix_seconds().ok()?)?;
+        Some(core::time::Duration::from_secs(seconds))
+    }
+
+    /// Get the number of whole days since 1970-01-01.
+    ///
+    /// Invalid fields could make the calculation overflow, so we check them
+    /// first.
+    fn days_since_epoch(&self) -> Result<u32, crate::Error> {
+        if !self.is_valid() {
+            return Err(crate::Error::InvalidArg);
+        }
+        Ok(days_from_civil(
+            u32::from(self.year()),
+            u32::from(self.month()),
+            u32::from(self.day()),
+        ))
+    }
+}
+
+impl core::fmt::Display for Time {
+    /// Formats the time according to ISO-8601, e.g. `2023-06-30T12:34:56`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year(),
+            self.month(),
+            self.day(),
+            self.hours,
+            self.minutes,
+            self.seconds
+        )
+    }
+}
+
+impl core::str::FromStr for Time {
+    type Err = crate::Error;
+
+    /// Parses an ISO-8601 time, e.g. `2023-06-30T12:34:56`.
+    ///
+    /// Only the `YYYY-MM-DDTHH:MM:SS` form is accepted.
+    fn from_str(s: &str) -> Result<Time, crate::Error> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 19
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || bytes[10] != b'T'
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return Err(crate::Error::InvalidArg);
+        }
+        let field = |start: usize, end: usize| -> Result<u16, crate::Error> {
+            let digits = &bytes[start..end];
+            if !digits.iter().all(u8::is_ascii_digit) {
+                return Err(crate::Error::InvalidArg);
+            }
+            Ok(digits
+                .iter()
+                .fold(0, |acc, digit| acc * 10 + u16::from(digit - b'0')))
+        };
+        Time::new(
+            field(0, 4)?,
+            field(5, 7)? as u8,
+            field(8, 10)? as u8,
+            field(11, 13)? as u8,
+            field(14, 16)? as u8,
+            field(17, 19)? as u8,
+        )
+    }
+}
+
+/// A day of the week.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Weekday {
+    /// Monday
+    Monday,
+    /// Tuesday
+    Tuesday,
+    /// Wednesday
+    Wednesday,
+    /// Thursday
+    Thursday,
+    /// Friday
+    Friday,
+    /// Saturday
+    Saturday,
+    /// Sunday
+    Sunday,
+}
+
+impl Weekday {
+    /// Get the weekday for a number of days since 1970-01-01 (which was a
+    /// Thursday).
+    fn from_days_since_epoch(days: u32) -> Weekday {
+        match (days + 3) % 7 {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+
+    /// Get the number of days since Monday (so Monday is `0` and Sunday is
+    /// `6`).
+    pub const fn days_from_monday(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Describes the system's local time zone.
+///
+/// Every [`Time`] passed over the API is in local time. Use
+/// [`TimeZone::local_to_utc`] and [`TimeZone::utc_to_local`] to convert
+/// timestamps when exchanging files with other machines.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeZone {
+    /// The offset of local standard time from UTC, in minutes.
+    ///
+    /// Zones east of Greenwich are positive, so Central European Time is `60`
+    /// and US Eastern Standard Time is `-300`.
+    pub utc_offset_minutes: i16,
+    /// The daylight saving rule for this zone, if it has one.
+    pub dst: FfiOption<DstRule>,
+}
+
+impl TimeZone {
+    /// Coordinated Universal Time, with no daylight saving.
+    pub const UTC: TimeZone = TimeZone {
+        utc_offset_minutes: 0,
+        dst: FfiOption::None,
+    };
+
+    /// Is daylight saving in force at the given instant?
+    ///
+    /// The given `utc` time must be in UTC. If it is not valid (see
+    /// [`Time::is_valid`]), you get `Err(Error::InvalidArg)`.
+    pub fn is_dst(&self, utc: &Time) -> Result<bool, crate::Error> {
+        let utc_seconds = utc.to_unix_seconds()?;
+        let FfiOption::Some(rule) = &self.dst else {
+            return Ok(false);
+        };
+        let standard = utc_seconds as i64 + i64::from(self.utc_offset_minutes) * 60;
+        let Some((start, end)) = rule.transitions(standard) else {
+            return Ok(false);
+        };
+        // The end of daylight saving is given in daylight time. Convert it to
+        // standard time so we can compare like with like.
+        let end = end - i64::from(rule.offset_minutes) * 60;
+        if start < end {
+            Ok((start..end).contains(&standard))
+        } else {
+            // Southern hemisphere - daylight saving spans the new year
+            Ok(standard >= start || standard < end)
+        }
+    }
+
+    /// Convert a UTC time to local time.
+    ///
+    /// You get `Err(Error::InvalidArg)` if `utc` is not valid, or if the
+    /// result cannot be represented by a [`Time`].
+    pub fn utc_to_local(&self, utc: &Time) -> Result<Time, crate::Error> {
+        let mut offset = i64::from(self.utc_offset_minutes) * 60;
+        if let (true, FfiOption::Some(rule)) = (self.is_dst(utc)?, &self.dst) {
+            offset += i64::from(rule.offset_minutes) * 60;
+        }
+        offset_time(utc, offset)
+    }
+
+    /// Convert a local time to UTC.
+    ///
+    /// Local times which occur twice (because the clocks went back) are taken
+    /// to be in daylight time. Local times which never occur (because the
+    /// clocks went forward) are also taken to be in daylight time.
+    ///
+    /// You get `Err(Error::InvalidArg)` if `local` is not valid, or if the
+    /// result cannot be represented by a [`Time`].
+    pub fn local_to_utc(&self, local: &Time) -> Result<Time, crate::Error> {
+        let mut offset = i64::from(self.utc_offset_minutes) * 60;
+        let wall = local.to_unix_seconds()? as i64;
+        if let FfiOption::Some(rule) = &self.dst {
+            if let Some((start, end)) = rule.transitions(wall) {
+                let in_dst = if start < end {
+                    (start..end).contains(&wall)
+                } else {
+                    wall >= start || wall < end
+                };
+                if in_dst {
+                    offset += i64::from(rule.offset_minutes) * 60;
+                }
+            }
+        }
+        offset_time(local, -offset)
+    }
+}
+
+/// Describes when daylight saving starts and ends each year.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DstRule {
+    /// How far the clocks go forward when daylight saving starts, in minutes.
+    ///
+    /// This is usually `60`.
+    pub offset_minutes: i16,
+    /// When daylight saving starts, in local standard time.
+    pub start: DstTransition,
+    /// When daylight saving ends, in local daylight time.
+    pub end: DstTransition,
+}
+
+impl DstRule {
+    /// The European Union rule: daylight saving runs from 01:00 UTC on the
+    /// last Sunday in March, to 01:00 UTC on the last Sunday in October.
+    ///
+    /// Because the transitions are in local time, this depends on the zone's
+    /// offset from UTC, which is given in minutes.
+    ///
+    /// # Panics
+    ///
+    /// The offset must be at least `-60` (the Azores) and less than `1320`,
+    /// or the transitions would fall on a different local day to the UTC one,
+    /// which a [`DstTransition`] cannot describe. The EU's own zones run from
+    /// `-60` to `120`.
+    pub const fn european_union(utc_offset_minutes: i16) -> DstRule {
+        let minutes = 60 + utc_offset_minutes as i32;
+        assert!(
+            minutes >= 0 && minutes + 60 < 1440,
+            "offset out of range for the EU daylight saving rule"
+        );
+        let minutes = minutes as u16;
+        DstRule {
+            offset_minutes: 60,
+            start: DstTransition {
+                month: 3,
+                week: DstTransition::LAST_WEEK,
+                weekday: Weekday::Sunday,
+                minutes_past_midnight: minutes,
+            },
+            end: DstTransition {
+                month: 10,
+                week: DstTransition::LAST_WEEK,
+                weekday: Weekday::Sunday,
+                minutes_past_midnight: minutes + 60,
+            },
+        }
+    }
+
+    /// The United States rule: daylight saving runs from 02:00 local time on
+    /// the second Sunday in March, to 02:00 local time on the first Sunday in
+    /// November.
+    pub const US: DstRule = DstRule {
+        offset_minutes: 60,
+        start: DstTransition {
+            month: 3,
+            week: 2,
+            weekday: Weekday::Sunday,
+            minutes_past_midnight: 120,
+        },
+        end: DstTransition {
+            month: 11,
+            week: 1,
+            weekday: Weekday::Sunday,
+            minutes_past_midnight: 120,
+        },
+    };
+
+    /// Get the start and end transitions for the year containing the given
+    /// wall-clock time, as seconds since 1970-01-01T00:00:00 on the wall
+    /// clock.
+    fn transitions(&self, wall_seconds: i64) -> Option<(i64, i64)> {
+        let now = Time::from_unix_seconds(u64::try_from(wall_seconds).ok()?).ok()?;
+        let year = now.year();
+        Some((self.start.wall_seconds(year)?, self.end.wall_seconds(year)?))
+    }
+}
+
+/// Describes the moment in a year when daylight saving starts or ends.
+///
+/// This is the same as the `Mm.w.d/time` form used in a POSIX `TZ` string.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DstTransition {
+    /// The calendar month, from `1` (January) to `12` (December).
+    pub month: u8,
+    /// Which occurrence of `weekday` in the month, from `1` to `4`.
+    ///
+    /// Use [`DstTransition::LAST_WEEK`] for the last occurrence in the month.
+    pub week: u8,
+    /// The day of the week the transition happens on.
+    pub weekday: Weekday,
+    /// The number of minutes past (local) midnight the transition happens.
+    pub minutes_past_midnight: u16,
+}
+
+impl DstTransition {
+    /// Use this as the `week` to select the last occurrence of a weekday.
+    pub const LAST_WEEK: u8 = 5;
+
+    /// Get the day of the month this transition falls on in the given year.
+    ///
+    /// You get `None` if the transition is not valid.
+    pub fn day_of_month(&self, year: u16) -> Option<u8> {
+        if !(1..=Self::LAST_WEEK).contains(&self.week) {
+            return None;
+        }
+        let first = Time::new(year, self.month, 1, 0, 0, 0).ok()?;
+        let first_weekday = first.day_of_week().ok()?.days_from_monday();
+        let target_weekday = self.weekday.days_from_monday();
+        let mut day = 1 + (7 + target_weekday - first_weekday) % 7 + (self.week - 1) * 7;
+        while day > days_in_month(year, self.month) {
+            day -= 7;
+        }
+        Some(day)
+    }
+
+    /// Get the wall-clock time of this transition in the given year, as
+    /// seconds since 1970-01-01T00:00:00 on the wall clock.
+    fn wall_seconds(&self, year: u16) -> Option<i64> {
+        let day = self.day_of_month(year)?;
+        let midnight = Time::new(year, self.month, day, 0, 0, 0).ok()?;
+        let midnight = midnight.to_unix_seconds().ok()? as i64;
+        Some(midnight + i64::from(self.minutes_past_midnight) * 60)
+    }
+}
+
+/// The kinds of advisory lock you can take on a file.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LockKind {
+    /// Any number of handles can hold a shared lock on the same bytes, but
+    /// only while nobody holds an exclusive lock on them. Use this for
+    /// reading.
+    Shared,
+    /// Only one handle can hold an exclusive lock on some bytes, and nobody
+    /// else can hold any lock on them. Use this for writing.
+    Exclusive,
+}
+
+/// A range of bytes in a file, for locking.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LockRange {
+    /// The offset of the first byte in the range
+    pub offset: u64,
+    /// How many bytes are in the range.
+    ///
+    /// The range may go past the end of the file.
+    pub length: u64,
+}
+
+impl LockRange {
+    /// A range covering the whole file, however long it gets.
+    pub const WHOLE_FILE: LockRange = LockRange {
+        offset: 0,
+        length: u64::MAX,
+    };
+
+    /// Make a range of `length` bytes, starting at `offset`.
+    pub const fn new(offset: u64, length: u64) -> LockRange {
+        LockRange { offset, length }
+    }
+
+    /// Get the offset just past the last byte in the range.
+    ///
+    /// Ranges which would go past the largest possible offset stop there.
+    pub const fn end(&self) -> u64 {
+        self.offset.saturating_add(self.length)
+    }
+
+    /// Do these two ranges have any bytes in common?
+    ///
+    /// An empty range overlaps nothing.
+    pub const fn overlaps(&self, other: &LockRange) -> bool {
+        self.length != 0
+            && other.length != 0
+            && self.offset < other.end()
+            && other.offset < self.end()
+    }
+}
+
 // ============================================================================
 // Functions
 // ============================================================================
 
-// None
+/// Move a time by the given number of seconds.
+fn offset_time(time: &Time, offset_seconds: i64) -> Result<Time, crate::Error> {
+    let seconds = (time.to_unix_seconds()? as i64)
+        .checked_add(offset_seconds)
+        .ok_or(crate::Error::InvalidArg)?;
+    let seconds = u64::try_from(seconds).map_err(|_| crate::Error::InvalidArg)?;
+    Time::from_unix_seconds(seconds)
+}
+
+/// Is the given calendar year a leap year?
+#[allow(clippy::manual_is_multiple_of)]
+pub const fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// How many days are in the given calendar month?
+///
+/// The `month` is from `1` (January) to `12` (December). You get `0` if the
+/// month is not valid.
+pub const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Convert a calendar date to the number of days since 1970-01-01.
+///
+/// This is Howard Hinnant's `days_from_civil` algorithm, restricted to valid
+/// dates on or after 1970-01-01. Anything else may overflow.
+fn days_from_civil(year: u32, month: u32, day: u32) -> u32 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - Time::UNIX_EPOCH_DAYS
+}
+
+/// Convert a number of days since 1970-01-01 to a calendar `(year, month,
+/// day)`.
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm. The `days` must be
+/// before the end of [`Time::MAX_YEAR`].
+fn civil_from_days(days: u32) -> (u32, u8, u8) {
+    let days = days + Time::UNIX_EPOCH_DAYS;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u32::from(month <= 2);
+    (year, month as u8, day as u8)
+}
 
 // ============================================================================
 // Tests
 // ============================================================================
 
-// None
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leap_years() {
+        assert!(is_leap_year(2000));
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2023));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2023, 13), 0);
+    }
+
+    #[test]
+    fn validation() {
+        assert!(Time::new(2023, 6, 30, 23, 59, 59).is_ok());
+        assert!(Time::new(2024, 2, 29, 0, 0, 0).is_ok());
+        assert_eq!(
+            Time::new(2023, 2, 29, 0, 0, 0),
+            Err(crate::Error::InvalidArg)
+        );
+        assert_eq!(
+            Time::new(2023, 0, 1, 0, 0, 0),
+            Err(crate::Error::InvalidArg)
+        );
+        assert_eq!(
+            Time::new(2023, 1, 0, 0, 0, 0),
+            Err(crate::Error::InvalidArg)
+        );
+        assert_eq!(
+            Time::new(2023, 1, 1, 24, 0, 0),
+            Err(crate::Error::InvalidArg)
+        );
+        assert_eq!(
+            Time::new(1969, 1, 1, 0, 0, 0),
+            Err(crate::Error::InvalidArg)
+        );
+        let bogus = Time {
+            year_since_1970: 53,
+            zero_indexed_month: 1,
+            zero_indexed_day: 29,
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+        };
+        assert!(!bogus.is_valid());
+    }
+
+    #[test]
+    fn day_of_week() {
+        let epoch = Time::new(1970, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(epoch.day_of_week(), Ok(Weekday::Thursday));
+        let time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        assert_eq!(time.day_of_week(), Ok(Weekday::Friday));
+        let time = Time::new(2024, 2, 29, 12, 0, 0).unwrap();
+        assert_eq!(time.day_of_week(), Ok(Weekday::Thursday));
+    }
+
+    #[test]
+    fn unix_seconds() {
+        let epoch = Time::new(1970, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(epoch.to_unix_seconds(), Ok(0));
+        let time = Time::new(2023, 6, 30, 12, 34, 56).unwrap();
+        assert_eq!(time.to_unix_seconds(), Ok(1_688_128_496));
+        assert_eq!(Time::from_unix_seconds(1_688_128_496), Ok(time));
+        let time = Time::new(2000, 2, 29, 0, 0, 0).unwrap();
+        assert_eq!(
+            Time::from_unix_seconds(time.to_unix_seconds().unwrap()),
+            Ok(time)
+        );
+        let last = Time::new(Time::MAX_YEAR, 12, 31, 23, 59, 59).unwrap();
+        assert_eq!(
+            Time::from_unix_seconds(last.to_unix_seconds().unwrap()),
+            Ok(last)
+        );
+        assert_eq!(
+            Time::from_unix_seconds(last.to_unix_seconds().unwrap() + 1),
+            Err(crate::Error::InvalidArg)
+        );
+    }
+
+    #[test]
+    fn fat_timestamps() {
+        let time = Time::new(2023, 6, 30, 12, 34, 57).unwrap();
+        let date_word = time.to_fat_date().unwrap();
+        let time_word = time.to_fat_time().unwrap();
+        assert_eq!(date_word, (43 << 9) | (6 << 5) | 30);
+        assert_eq!(time_word, (12 << 11) | (34 << 5) | 28);
+        assert_eq!(
+            Time::from_fat(date_word, time_word),
+            Time::new(2023, 6, 30, 12, 34, 56)
+        );
+        let old = Time::new(1979, 12, 31, 0, 0, 0).unwrap();
+        assert_eq!(old.to_fat_date(), Err(crate::Error::InvalidArg));
+        // Month 0 is not valid
+        assert_eq!(Time::from_fat(0, 0), Err(crate::Error::InvalidArg));
+    }
+
+    #[test]
+    fn durations() {
+        use core::time::Duration;
+        let time = Time::new(2023, 12, 31, 23, 59, 30).unwrap();
+        let later = time.checked_add(Duration::from_secs(45)).unwrap();
+        assert_eq!(later, Time::new(2024, 1, 1, 0, 0, 15).unwrap());
+        assert_eq!(later.checked_sub(Duration::from_secs(45)), Some(time));
+        assert_eq!(later.duration_since(&time), Some(Duration::from_secs(45)));
+        assert_eq!(time.duration_since(&later), None);
+        let epoch = Time::new(1970, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(epoch.checked_sub(Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn transitions() {
+        let rule = DstRule::european_union(60);
+        assert_eq!(rule.start.day_of_month(2023), Some(26));
+        assert_eq!(rule.end.day_of_month(2023), Some(29));
+        assert_eq!(DstRule::US.start.day_of_month(2023), Some(12));
+        assert_eq!(DstRule::US.end.day_of_month(2023), Some(5));
+        let bad = DstTransition {
+            week: 0,
+            ..rule.start
+        };
+        assert_eq!(bad.day_of_month(2023), None);
+    }
+
+    #[test]
+    fn northern_time_zone() {
+        let cet = TimeZone {
+            utc_offset_minutes: 60,
+            dst: FfiOption::Some(DstRule::european_union(60)),
+        };
+        // Winter
+        let utc = Time::new(2023, 1, 15, 12, 0, 0).unwrap();
+        assert!(!cet.is_dst(&utc).unwrap());
+        let local = Time::new(2023, 1, 15, 13, 0, 0).unwrap();
+        assert_eq!(cet.utc_to_local(&utc), Ok(local));
+        assert_eq!(cet.local_to_utc(&local), Ok(utc));
+        // Summer
+        let utc = Time::new(2023, 7, 15, 12, 0, 0).unwrap();
+        assert!(cet.is_dst(&utc).unwrap());
+        let local = Time::new(2023, 7, 15, 14, 0, 0).unwrap();
+        assert_eq!(cet.utc_to_local(&utc), Ok(local));
+        assert_eq!(cet.local_to_utc(&local), Ok(utc));
+        // Either side of the spring transition at 01:00 UTC
+        assert!(!cet
+            .is_dst(&Time::new(2023, 3, 26, 0, 59, 59).unwrap())
+            .unwrap());
+        assert!(cet
+            .is_dst(&Time::new(2023, 3, 26, 1, 0, 0).unwrap())
+            .unwrap());
+        // Either side of the autumn transition at 01:00 UTC
+        assert!(cet
+            .is_dst(&Time::new(2023, 10, 29, 0, 59, 59).unwrap())
+            .unwrap());
+        assert!(!cet
+            .is_dst(&Time::new(2023, 10, 29, 1, 0, 0).unwrap())
+            .unwrap());
+    }
+
+    #[test]
+    fn southern_time_zone() {
+        let sydney = TimeZone {
+            utc_offset_minutes: 600,
+            dst: FfiOption::Some(DstRule {
+                offset_minutes: 60,
+                start: DstTransition {
+                    month: 10,
+                    week: 1,
+                    weekday: Weekday::Sunday,
+                    minutes_past_midnight: 120,
+                },
+                end: DstTransition {
+                    month: 4,
+                    week: 1,
+                    weekday: Weekday::Sunday,
+                    minutes_past_midnight: 180,
+                },
+            }),
+        };
+        let utc = Time::new(2023, 1, 15, 0, 0, 0).unwrap();
+        assert!(sydney.is_dst(&utc).unwrap());
+        assert_eq!(sydney.utc_to_local(&utc), Time::new(2023, 1, 15, 11, 0, 0));
+        let utc = Time::new(2023, 7, 15, 0, 0, 0).unwrap();
+        assert!(!sydney.is_dst(&utc).unwrap());
+        let local = Time::new(2023, 7, 15, 10, 0, 0).unwrap();
+        assert_eq!(sydney.utc_to_local(&utc), Ok(local));
+        assert_eq!(sydney.local_to_utc(&local), Ok(utc));
+    }
+
+    #[test]
+    fn utc_time_zone() {
+        let time = Time::new(2023, 7, 15, 0, 0, 0).unwrap();
+        assert!(!TimeZone::UTC.is_dst(&time).unwrap());
+        assert_eq!(TimeZone::UTC.utc_to_local(&time), Ok(time));
+        let epoch = Time::new(1970, 1, 1, 0, 0, 0).unwrap();
+        let west = TimeZone {
+            utc_offset_minutes: -300,
+            dst: FfiOption::None,
+        };
+        assert_eq!(west.utc_to_local(&epoch), Err(crate::Error::InvalidArg));
+    }
+
+    #[test]
+    fn iso8601() {
+        extern crate std;
+        use std::string::ToString;
+        let time = Time::new(2023, 6, 3, 1, 2, 3).unwrap();
+        assert_eq!(time.to_string(), "2023-06-03T01:02:03");
+        assert_eq!("2023-06-03T01:02:03".parse::<Time>(), Ok(time));
+        assert!("2023-06-03 01:02:03".parse::<Time>().is_err());
+        assert!("2023-02-30T01:02:03".parse::<Time>().is_err());
+        assert!("2023-6-3T1:2:3".parse::<Time>().is_err());
+        assert!("+023-06-03T01:02:03".parse::<Time>().is_err());
+    }
+
+    #[test]
+    fn garbage_fields() {
+        let mut time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        time.zero_indexed_month = 255;
+        assert!(!time.is_valid());
+        assert_eq!(time.month(), 0);
+        let mut time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        time.zero_indexed_day = 255;
+        assert!(!time.is_valid());
+        assert_eq!(time.day(), 0);
+        for field in 0..4 {
+            let mut time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+            match field {
+                0 => time.hours = 255,
+                1 => time.minutes = 255,
+                2 => time.seconds = 255,
+                _ => time.year_since_1970 = 255,
+            }
+            assert_eq!(time.is_valid(), field == 3);
+        }
+    }
+
+    #[test]
+    fn huge_unix_seconds() {
+        for seconds in [
+            u64::from(u32::MAX) * 86_400,
+            u64::from(u32::MAX - 719_468) * 86_400,
+            u64::MAX,
+        ] {
+            assert_eq!(
+                Time::from_unix_seconds(seconds),
+                Err(crate::Error::InvalidArg)
+            );
+        }
+    }
+
+    #[test]
+    fn stat_kinds() {
+        let stat = |attr| Stat {
+            attr,
+            ..Default::default()
+        };
+        assert!(stat(Attributes::ARCHIVE).is_file());
+        assert!(!stat(Attributes::ARCHIVE).is_dir());
+        assert!(stat(Attributes::DIRECTORY).is_dir());
+        assert!(!stat(Attributes::DIRECTORY).is_file());
+        assert!(stat(Attributes::DEVICE).is_device());
+        assert!(!stat(Attributes::DEVICE).is_file());
+        assert!(!stat(Attributes::VOLUME).is_file());
+        assert!(stat(Attributes::VOLUME).is_volume_label());
+        let attr = Attributes::READ_ONLY | Attributes::HIDDEN | Attributes::SYSTEM;
+        assert!(stat(attr).is_file());
+        assert!(stat(attr).is_read_only());
+        assert!(stat(attr).is_hidden());
+        assert!(stat(attr).is_system());
+        assert!(!stat(Attributes::empty()).is_read_only());
+    }
+
+    #[test]
+    fn stat_drive_name() {
+        let mut stat = Stat::default();
+        assert_eq!(stat.drive_name(), "");
+        stat.drive[..3].copy_from_slice(b"HD0");
+        assert_eq!(stat.drive_name(), "HD0");
+        stat.drive = *b"FLOPPY10";
+        assert_eq!(stat.drive_name(), "FLOPPY10");
+    }
+
+    #[test]
+    fn lock_ranges() {
+        let range = LockRange::new(100, 50);
+        assert_eq!(range.end(), 150);
+        assert!(range.overlaps(&LockRange::new(149, 1)));
+        assert!(range.overlaps(&LockRange::new(0, 101)));
+        assert!(!range.overlaps(&LockRange::new(150, 10)));
+        assert!(!range.overlaps(&LockRange::new(0, 100)));
+        assert!(!range.overlaps(&LockRange::new(120, 0)));
+        assert!(LockRange::WHOLE_FILE.overlaps(&range));
+        assert!(LockRange::new(u64::MAX - 1, 10).overlaps(&LockRange::WHOLE_FILE));
+        assert_eq!(LockRange::new(u64::MAX - 1, 10).end(), u64::MAX);
+    }
+
+    /// Open `HD0:/A.TXT` twice
+    fn two_handles() -> (crate::Api, Handle, Handle) {
+        crate::testing::reset(&["HD0:/A.TXT"]);
+        let api = crate::testing::api();
+        let first = Result::from((api.open)("HD0:/A.TXT".into(), Flags::WRITE)).unwrap();
+        let second = Result::from((api.open)("HD0:/A.TXT".into(), Flags::empty())).unwrap();
+        (api, first, second)
+    }
+
+    #[test]
+    fn try_lock_conflicts() {
+        let (api, first, second) = two_handles();
+        let try_lock = |fd, kind, offset, length| {
+            Result::from((api.try_lock)(fd, kind, LockRange::new(offset, length)))
+        };
+        assert_eq!(try_lock(first, LockKind::Exclusive, 0, 10), Ok(()));
+        assert_eq!(
+            try_lock(second, LockKind::Shared, 5, 10),
+            Err(crate::Error::WouldBlock)
+        );
+        // Touching is not overlapping
+        assert_eq!(try_lock(second, LockKind::Exclusive, 10, 10), Ok(()));
+        // Shared locks can overlap each other
+        assert_eq!(try_lock(first, LockKind::Shared, 30, 10), Ok(()));
+        assert_eq!(try_lock(second, LockKind::Shared, 35, 10), Ok(()));
+        assert_eq!(
+            try_lock(second, LockKind::Exclusive, 39, 1),
+            Err(crate::Error::WouldBlock)
+        );
+        // Our own locks never get in the way - they are replaced
+        assert_eq!(try_lock(first, LockKind::Exclusive, 0, 10), Ok(()));
+        assert_eq!(try_lock(first, LockKind::Shared, 0, 10), Ok(()));
+        assert_eq!(try_lock(second, LockKind::Shared, 0, 10), Ok(()));
+        // `lock` can't wait in the fake, so it fails the same way
+        assert_eq!(
+            Result::from((api.lock)(
+                first,
+                LockKind::Exclusive,
+                LockRange::new(15, 1)
+            )),
+            Err(crate::Error::WouldBlock)
+        );
+        let closed = Handle::new(99);
+        assert_eq!(
+            try_lock(closed, LockKind::Shared, 0, 1),
+            Err(crate::Error::BadHandle)
+        );
+    }
+
+    #[test]
+    fn lock_whole_file() {
+        let (api, first, second) = two_handles();
+        let try_lock = |fd, kind, range| Result::from((api.try_lock)(fd, kind, range));
+        assert_eq!(
+            try_lock(first, LockKind::Shared, LockRange::WHOLE_FILE),
+            Ok(())
+        );
+        assert_eq!(
+            try_lock(second, LockKind::Shared, LockRange::WHOLE_FILE),
+            Ok(())
+        );
+        let last_byte = LockRange::new(u64::MAX - 1, 1);
+        assert_eq!(
+            try_lock(second, LockKind::Exclusive, last_byte),
+            Err(crate::Error::WouldBlock)
+        );
+        // Closing a handle releases all of its locks
+        Result::from((api.close)(first)).unwrap();
+        assert_eq!(
+            try_lock(second, LockKind::Exclusive, LockRange::WHOLE_FILE),
+            Ok(())
+        );
+        Result::from((api.close)(second)).unwrap();
+    }
+
+    #[test]
+    fn unlock_releases() {
+        let (api, first, second) = two_handles();
+        let try_lock = |fd, offset, length| {
+            Result::from((api.try_lock)(
+                fd,
+                LockKind::Exclusive,
+                LockRange::new(offset, length),
+            ))
+        };
+        let unlock = |fd, range| Result::from((api.unlock)(fd, range));
+        Result::from((api.try_lock)(
+            first,
+            LockKind::Exclusive,
+            LockRange::WHOLE_FILE,
+        ))
+        .unwrap();
+        assert_eq!(try_lock(second, 0, 1), Err(crate::Error::WouldBlock));
+        // Unlocking the middle of a lock leaves both ends locked
+        assert_eq!(unlock(first, LockRange::new(100, 100)), Ok(()));
+        assert_eq!(try_lock(second, 100, 100), Ok(()));
+        assert_eq!(try_lock(second, 99, 1), Err(crate::Error::WouldBlock));
+        assert_eq!(try_lock(second, 200, 1), Err(crate::Error::WouldBlock));
+        assert_eq!(unlock(first, LockRange::WHOLE_FILE), Ok(()));
+        assert_eq!(try_lock(second, 0, 1000), Ok(()));
+        // Unlocking bytes which are not locked is fine
+        assert_eq!(unlock(first, LockRange::new(5000, 1)), Ok(()));
+        assert_eq!(
+            unlock(Handle::new(99), LockRange::WHOLE_FILE),
+            Err(crate::Error::BadHandle)
+        );
+    }
+
+    #[test]
+    fn open_twice() {
+        let (api, first, second) = two_handles();
+        let open = |flags| Result::from((api.open)("HD0:/A.TXT".into(), flags));
+        assert_ne!(first, second);
+        // Can't truncate while someone else has it open
+        assert_eq!(
+            open(Flags::WRITE | Flags::TRUNCATE),
+            Err(crate::Error::InvalidArg)
+        );
+        Result::from((api.close)(first)).unwrap();
+        Result::from((api.close)(second)).unwrap();
+        let third = open(Flags::WRITE | Flags::TRUNCATE).unwrap();
+        // Deleted when the last handle closes, whichever that is
+        let fourth = open(Flags::DELETE_ON_CLOSE).unwrap();
+        Result::from((api.close)(fourth)).unwrap();
+        assert_eq!(crate::testing::paths(), ["HD0:", "HD0:/A.TXT"]);
+        Result::from((api.close)(third)).unwrap();
+        assert_eq!(crate::testing::paths(), ["HD0:"]);
+    }
+
+    #[test]
+    fn garbage_fields_everywhere() {
+        let good = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        let mut day = Time::new(1970, 1, 1, 0, 0, 0).unwrap();
+        day.zero_indexed_day = 255;
+        let mut month = good;
+        month.zero_indexed_month = 255;
+        let mut hours = good;
+        hours.hours = 255;
+        let cet = TimeZone {
+            utc_offset_minutes: 60,
+            dst: FfiOption::Some(DstRule::european_union(60)),
+        };
+        let second = core::time::Duration::from_secs(1);
+        for bad in [day, month, hours] {
+            assert_eq!(bad.to_unix_seconds(), Err(crate::Error::InvalidArg));
+            assert_eq!(bad.day_of_week(), Err(crate::Error::InvalidArg));
+            assert_eq!(bad.checked_add(second), None);
+            assert_eq!(bad.checked_sub(second), None);
+            assert_eq!(bad.duration_since(&good), None);
+            assert_eq!(good.duration_since(&bad), None);
+            for tz in [&TimeZone::UTC, &cet] {
+                assert_eq!(tz.is_dst(&bad), Err(crate::Error::InvalidArg));
+                assert_eq!(tz.utc_to_local(&bad), Err(crate::Error::InvalidArg));
+                assert_eq!(tz.local_to_utc(&bad), Err(crate::Error::InvalidArg));
+            }
+        }
+    }
+
+    #[test]
+    fn fat_garbage_fields() {
+        let mut time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        time.zero_indexed_day = 199;
+        assert_eq!(time.to_fat_date(), Err(crate::Error::InvalidArg));
+        assert_eq!(time.to_fat_time(), Err(crate::Error::InvalidArg));
+        let mut time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        time.minutes = 200;
+        assert_eq!(time.to_fat_date(), Err(crate::Error::InvalidArg));
+        assert_eq!(time.to_fat_time(), Err(crate::Error::InvalidArg));
+        let mut time = Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        time.zero_indexed_month = 12;
+        assert_eq!(time.to_fat_date(), Err(crate::Error::InvalidArg));
+        assert_eq!(time.to_fat_time(), Err(crate::Error::InvalidArg));
+    }
+
+    #[test]
+    fn negative_offset_time_zone() {
+        // The Azores are an hour behind UTC, so the clocks change at midnight
+        let azores = TimeZone {
+            utc_offset_minutes: -60,
+            dst: FfiOption::Some(DstRule::european_union(-60)),
+        };
+        let FfiOption::Some(rule) = &azores.dst else {
+            panic!("no rule");
+        };
+        assert_eq!(rule.start.minutes_past_midnight, 0);
+        assert_eq!(rule.end.minutes_past_midnight, 60);
+        // Either side of the spring transition at 01:00 UTC
+        let before = Time::new(2023, 3, 26, 0, 59, 59).unwrap();
+        assert!(!azores.is_dst(&before).unwrap());
+        let after = Time::new(2023, 3, 26, 1, 0, 0).unwrap();
+        assert!(azores.is_dst(&after).unwrap());
+        assert_eq!(
+            azores.utc_to_local(&after),
+            Ok(Time::new(2023, 3, 26, 1, 0, 0).unwrap())
+        );
+        // Either side of the autumn transition at 01:00 UTC
+        let before = Time::new(2023, 10, 29, 0, 59, 59).unwrap();
+        assert!(azores.is_dst(&before).unwrap());
+        let after = Time::new(2023, 10, 29, 1, 0, 0).unwrap();
+        assert!(!azores.is_dst(&after).unwrap());
+        assert_eq!(
+            azores.utc_to_local(&after),
+            Ok(Time::new(2023, 10, 29, 0, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "offset out of range")]
+    fn european_union_far_west() {
+        let _ = DstRule::european_union(-600);
+    }
+}
 
 // ============================================================================
 // End of File
@@ -0,0 +1,774 @@
+//! Helpers for working with files and directories, built on the [`Api`].
+//!
+//! [`Api`]: crate::Api
+
+// ============================================================================
+// Imports
+// ============================================================================
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::dir::{Filter, ReadDir, WalkOrder, Walker, MAX_WALK_DEPTH};
+use crate::file::{Attributes, CopyFlags, Flags, Handle};
+use crate::path::{Path, PathBuf};
+use crate::{Api, Error};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// How many different names a [`TempFile`] can have.
+///
+/// Names run from `~T000000.TMP` to `~T999999.TMP`.
+const MAX_TEMP_FILES: u32 = 1_000_000;
+
+// ============================================================================
+// Global Variables
+// ============================================================================
+
+/// Which name the next [`TempFile`] tries first.
+///
+/// This only saves us from probing the same names over and over -
+/// [`Flags::CREATE_NEW`] is what makes the name unique. We use a plain load
+/// and store because not every target has atomic read-modify-write.
+static NEXT_TEMP_FILE: AtomicU32 = AtomicU32::new(0);
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Describes why [`remove_dir_all`] failed, and where.
+///
+/// Converts into the [`Error`] it holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoveError<const N: usize> {
+    /// The file or directory which could not be removed.
+    ///
+    /// This is `None` if the path given to [`remove_dir_all`] could not be
+    /// turned into an absolute path.
+    pub path: Option<PathBuf<N>>,
+    /// What went wrong.
+    ///
+    /// A read-only entry gives [`Error::FileReadOnly`].
+    pub error: Error,
+}
+
+impl<const N: usize> core::fmt::Display for RemoveError<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "cannot remove {}: {:?}", path, self.error),
+            None => write!(f, "cannot remove: {:?}", self.error),
+        }
+    }
+}
+
+impl<const N: usize> From<RemoveError<N>> for Error {
+    fn from(value: RemoveError<N>) -> Error {
+        value.error
+    }
+}
+
+/// A newly created file with a unique name, for scratch data.
+///
+/// The file is created in a directory of your choosing, with a name like
+/// `~T000042.TMP` which is valid on any FAT volume. It is created with
+/// [`Flags::CREATE_NEW`], so an existing file is never opened or replaced,
+/// even if another application is creating temporary files at the same
+/// time.
+///
+/// The file is closed when this object is dropped.
+///
+/// ```no_run
+/// # use neotron_api::{Api, fs::TempFile};
+/// # fn example(api: &Api) -> Result<(), neotron_api::Error> {
+/// let temp = TempFile::<64>::create(api, "HD0:/TEMP", true)?;
+/// let _ = (api.write)(temp.handle(), neotron_api::FfiByteSlice::new(b"scratch"));
+/// // The file is deleted when `temp` is dropped
+/// # Ok(())
+/// # }
+/// ```
+pub struct TempFile<'a, const N: usize> {
+    api: &'a Api,
+    /// The absolute path of the file
+    path: PathBuf<N>,
+    /// The open file
+    handle: Handle,
+}
+
+impl<'a, const N: usize> TempFile<'a, N> {
+    /// Create and open a new temporary file in the given directory.
+    ///
+    /// The file is opened for reading and writing. If `delete_on_close` is
+    /// set, the file is opened with [`Flags::DELETE_ON_CLOSE`] so the OS
+    /// deletes it when it is closed.
+    ///
+    /// Each call starts looking just after the name the last call picked, and
+    /// wraps around after `~T999999.TMP`.
+    ///
+    /// The absolute path of the file must fit in `N` bytes. If every name is
+    /// taken, you get `Err(Error::AlreadyExists)`.
+    pub fn create(
+        api: &'a Api,
+        dir: &str,
+        delete_on_close: bool,
+    ) -> Result<TempFile<'a, N>, Error> {
+        let dir: PathBuf<N> = Path::new(dir)?.absolute(api)?;
+        let mut flags = Flags::WRITE | Flags::CREATE_NEW;
+        if delete_on_close {
+            flags |= Flags::DELETE_ON_CLOSE;
+        }
+        let start = NEXT_TEMP_FILE.load(Ordering::Relaxed) % MAX_TEMP_FILES;
+        for offset in 0..MAX_TEMP_FILES {
+            let number = (start + offset) % MAX_TEMP_FILES;
+            let mut name = *b"~T000000.TMP";
+            let mut value = number;
+            for digit in name[2..8].iter_mut().rev() {
+                *digit = b'0' + (value % 10) as u8;
+                value /= 10;
+            }
+            let mut path = dir.clone();
+            path.push(core::str::from_utf8(&name).map_err(|_| Error::InvalidPath)?)?;
+            match Result::from((api.open)(path.as_path().as_ffi_string(), flags)) {
+                Ok(handle) => {
+                    NEXT_TEMP_FILE.store((number + 1) % MAX_TEMP_FILES, Ordering::Relaxed);
+                    return Ok(TempFile { api, path, handle });
+                }
+                Err(Error::AlreadyExists) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::AlreadyExists)
+    }
+
+    /// Get the handle for the open file.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Get the absolute path of the file.
+    pub fn path(&self) -> Path<'_> {
+        self.path.as_path()
+    }
+
+    /// Close the file, reporting any error.
+    pub fn close(self) -> Result<(), Error> {
+        let result = (self.api.close)(self.handle);
+        // We've closed it, so don't close it again
+        core::mem::forget(self);
+        result.into()
+    }
+
+    /// Take the handle, so the file is not closed when this is dropped.
+    ///
+    /// You must close the handle yourself.
+    pub fn into_handle(self) -> Handle {
+        let handle = self.handle;
+        core::mem::forget(self);
+        handle
+    }
+}
+
+impl<'a, const N: usize> Drop for TempFile<'a, N> {
+    fn drop(&mut self) {
+        let _ = (self.api.close)(self.handle);
+    }
+}
+
+// ============================================================================
+// Functions
+// ============================================================================
+
+/// Move a file or directory, even to a different drive.
+///
+/// If both paths are on the same drive, this is just
+/// [`Api::rename`](crate::Api::rename). Otherwise the file (or the directory
+/// and everything in it) is copied, keeping the attributes and timestamps of
+/// every file and directory, and then the original is deleted. Both paths, and every path
+/// inside a directory being moved, must fit in `N` bytes.
+///
+/// If `new_path` already exists, you get `Err(Error::AlreadyExists)`.
+///
+/// If the copy fails part-way, whatever was copied is deleted again and the
+/// original is left alone. If deleting the original directory fails
+/// part-way, the copy is complete, but some of the original will be left
+/// behind.
+///
+/// # Limitations
+///
+/// * A directory can only be moved to another drive if it has no more than
+///   [`MAX_WALK_DEPTH`] levels of directories inside it. Otherwise you get
+///   `Err(Error::InvalidArg)`, and nothing is moved.
+/// * Nothing being moved may be open.
+pub fn move_path<const N: usize>(api: &Api, old_path: &str, new_path: &str) -> Result<(), Error> {
+    let old_path: PathBuf<N> = Path::new(old_path)?.absolute(api)?;
+    let new_path: PathBuf<N> = Path::new(new_path)?.absolute(api)?;
+    let mut new_parent = new_path.clone();
+    if !new_parent.pop() {
+        return Err(Error::InvalidPath);
+    }
+    let stat = Result::from((api.stat)(old_path.as_path().as_ffi_string()))?;
+    let parent_stat = Result::from((api.stat)(new_parent.as_path().as_ffi_string()))?;
+    if stat
+        .drive_name()
+        .eq_ignore_ascii_case(parent_stat.drive_name())
+    {
+        return (api.rename)(
+            old_path.as_path().as_ffi_string(),
+            new_path.as_path().as_ffi_string(),
+        )
+        .into();
+    }
+    match Result::from((api.stat)(new_path.as_path().as_ffi_string())) {
+        Ok(_) => return Err(Error::AlreadyExists),
+        Err(Error::NotFound) => {}
+        Err(e) => return Err(e),
+    }
+    if stat.is_dir() {
+        copy_tree(api, &old_path, &new_path)?;
+        remove_tree(api, &old_path, true).map_err(Error::from)
+    } else {
+        copy_one_file(api, &old_path, &new_path)?;
+        let result = force_delete_file(api, &old_path);
+        if result.is_err() {
+            let _ = force_delete_file(api, &new_path);
+        }
+        result
+    }
+}
+
+/// Copy a single file, keeping its attributes and timestamps.
+///
+/// If the copy fails, any partial copy is deleted.
+fn copy_one_file<const N: usize>(
+    api: &Api,
+    old_path: &PathBuf<N>,
+    new_path: &PathBuf<N>,
+) -> Result<(), Error> {
+    let flags = CopyFlags::PRESERVE_ATTRIBUTES | CopyFlags::PRESERVE_TIMES;
+    match Result::from((api.copy_file)(
+        old_path.as_path().as_ffi_string(),
+        new_path.as_path().as_ffi_string(),
+        flags,
+    )) {
+        Ok(_) => Ok(()),
+        // Whatever is there already isn't ours to delete
+        Err(Error::AlreadyExists) => Err(Error::AlreadyExists),
+        Err(e) => {
+            let _ = force_delete_file(api, new_path);
+            Err(e)
+        }
+    }
+}
+
+/// Copy a directory and everything in it.
+///
+/// If the copy fails, everything copied so far is deleted.
+fn copy_tree<const N: usize>(
+    api: &Api,
+    old_path: &PathBuf<N>,
+    new_path: &PathBuf<N>,
+) -> Result<(), Error> {
+    Result::from((api.mkdir)(new_path.as_path().as_ffi_string()))?;
+    let result = copy_contents(api, old_path, new_path)
+        .and_then(|_| copy_dir_metadata(api, old_path, new_path));
+    if let Err(e) = result {
+        let _ = remove_tree(api, new_path, true);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Copy everything inside one directory into another, which must exist.
+fn copy_contents<const N: usize>(
+    api: &Api,
+    old_path: &PathBuf<N>,
+    new_path: &PathBuf<N>,
+) -> Result<(), Error> {
+    for item in Walker::<N>::new(api, old_path.as_str(), WalkOrder::DepthFirst)? {
+        let item = item?;
+        if item.stat.is_dir() && item.depth >= MAX_WALK_DEPTH {
+            // The walker won't look inside, so we can't copy what's there
+            return Err(Error::InvalidArg);
+        }
+        let target = target_path(old_path, new_path, &item.path)?;
+        if item.stat.is_dir() {
+            Result::from((api.mkdir)(target.as_path().as_ffi_string()))?;
+        } else {
+            copy_one_file(api, &item.path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Give every directory in a copied tree the attributes and timestamps of
+/// the directory it was copied from.
+///
+/// This is done once everything has been copied, because adding things to a
+/// directory changes its modification time.
+fn copy_dir_metadata<const N: usize>(
+    api: &Api,
+    old_path: &PathBuf<N>,
+    new_path: &PathBuf<N>,
+) -> Result<(), Error> {
+    for item in Walker::<N>::new(api, old_path.as_str(), WalkOrder::DepthFirst)? {
+        let item = item?;
+        if item.stat.is_dir() {
+            let target = target_path(old_path, new_path, &item.path)?;
+            set_metadata(api, &target, &item.stat)?;
+        }
+    }
+    let stat = Result::from((api.stat)(old_path.as_path().as_ffi_string()))?;
+    set_metadata(api, new_path, &stat)
+}
+
+/// Give a file or directory the given attributes and timestamps.
+fn set_metadata<const N: usize>(
+    api: &Api,
+    path: &PathBuf<N>,
+    stat: &crate::file::Stat,
+) -> Result<(), Error> {
+    let path = path.as_path();
+    Result::from((api.set_times)(
+        path.as_ffi_string(),
+        stat.ctime,
+        stat.mtime,
+    ))?;
+    Result::from((api.set_attributes)(path.as_ffi_string(), stat.attr))
+}
+
+/// Work out where something inside `old_path` goes inside `new_path`.
+fn target_path<const N: usize>(
+    old_path: &PathBuf<N>,
+    new_path: &PathBuf<N>,
+    item_path: &PathBuf<N>,
+) -> Result<PathBuf<N>, Error> {
+    let relative = item_path.as_str()[old_path.as_str().len()..].trim_start_matches('/');
+    let mut target = new_path.clone();
+    target.push(relative)?;
+    Ok(target)
+}
+
+/// Delete a directory and everything in it.
+///
+/// Every path inside the directory must fit in `N` bytes. Only one
+/// directory handle is used at a time, so this works however deep the tree
+/// goes.
+///
+/// Read-only files and directories are not deleted unless `force` is set -
+/// you get an error with [`Error::FileReadOnly`] instead. With `force`, the
+/// `READ_ONLY` attribute of each one is cleared (with
+/// [`Api::set_attributes`](crate::Api::set_attributes)) just before it is
+/// deleted.
+///
+/// If `path` is a file rather than a directory, or is the root of a drive
+/// (like `HD0:/`), you get an error with [`Error::InvalidPath`] and nothing
+/// is changed.
+///
+/// If anything cannot be deleted, the error says which path it was.
+/// Everything deleted before that point stays deleted.
+///
+/// ```no_run
+/// # use neotron_api::{Api, fs::remove_dir_all};
+/// # fn example(api: &Api) {
+/// if let Err(e) = remove_dir_all::<64>(api, "HD0:/OLDSTUFF", false) {
+///     // e.g. "cannot remove HD0:/OLDSTUFF/README.TXT: FileReadOnly"
+///     // ... report e ...
+/// }
+/// # }
+/// ```
+pub fn remove_dir_all<const N: usize>(
+    api: &Api,
+    path: &str,
+    force: bool,
+) -> Result<(), RemoveError<N>> {
+    let path: PathBuf<N> = Path::new(path)
+        .and_then(|path| path.absolute(api))
+        .map_err(|error| RemoveError { path: None, error })?;
+    remove_tree(api, &path, force)
+}
+
+/// Delete a directory and everything in it.
+///
+/// See [`remove_dir_all`].
+fn remove_tree<const N: usize>(
+    api: &Api,
+    path: &PathBuf<N>,
+    force: bool,
+) -> Result<(), RemoveError<N>> {
+    let fail = |path: &PathBuf<N>, error| RemoveError {
+        path: Some(path.clone()),
+        error,
+    };
+    // A drive root can't be deleted, so don't empty it and then fail
+    if path.as_path().parent().is_none() {
+        return Err(fail(path, Error::InvalidPath));
+    }
+    let stat =
+        Result::from((api.stat)(path.as_path().as_ffi_string())).map_err(|e| fail(path, e))?;
+    if !stat.is_dir() {
+        return Err(fail(path, Error::InvalidPath));
+    }
+    if stat.is_read_only() && !force {
+        return Err(fail(path, Error::FileReadOnly));
+    }
+    make_writable(api, path, stat.attr).map_err(|e| fail(path, e))?;
+    let mut path = path.clone();
+    let mut depth = 0;
+    let filter = Filter {
+        dot_entries: false,
+        ..Filter::all()
+    };
+    loop {
+        // Deleting entries while reading a directory isn't allowed, so we
+        // look at the first entry and then start again.
+        let first = ReadDir::new(api, path.as_str(), filter.clone())
+            .and_then(|mut entries| entries.next().transpose())
+            .map_err(|e| fail(&path, e))?;
+        let Some(entry) = first else {
+            Result::from((api.deletedir)(path.as_path().as_ffi_string()))
+                .map_err(|e| fail(&path, e))?;
+            if depth == 0 {
+                return Ok(());
+            }
+            path.pop();
+            depth -= 1;
+            continue;
+        };
+        let mut child = path.clone();
+        child.push(entry.name_str()).map_err(|e| fail(&path, e))?;
+        if entry.properties.is_read_only() && !force {
+            return Err(fail(&child, Error::FileReadOnly));
+        }
+        make_writable(api, &child, entry.properties.attr).map_err(|e| fail(&child, e))?;
+        if entry.properties.is_dir() {
+            path = child;
+            depth += 1;
+        } else {
+            Result::from((api.deletefile)(child.as_path().as_ffi_string()))
+                .map_err(|e| fail(&child, e))?;
+        }
+    }
+}
+
+/// Delete a file, even if it is read-only.
+fn force_delete_file<const N: usize>(api: &Api, path: &PathBuf<N>) -> Result<(), Error> {
+    let stat = Result::from((api.stat)(path.as_path().as_ffi_string()))?;
+    make_writable(api, path, stat.attr)?;
+    Result::from((api.deletefile)(path.as_path().as_ffi_string()))
+}
+
+/// Clear the `READ_ONLY` attribute of something with the given attributes, so
+/// the OS will let us delete it.
+fn make_writable<const N: usize>(
+    api: &Api,
+    path: &PathBuf<N>,
+    attr: Attributes,
+) -> Result<(), Error> {
+    if attr.contains(Attributes::READ_ONLY) {
+        Result::from((api.set_attributes)(
+            path.as_path().as_ffi_string(),
+            attr - Attributes::READ_ONLY,
+        ))?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::file::{Stat, Time};
+    use crate::testing;
+
+    fn move_path(old_path: &str, new_path: &str) -> Result<(), Error> {
+        super::move_path::<64>(&testing::api(), old_path, new_path)
+    }
+
+    fn stat(path: &str) -> Result<Stat, Error> {
+        (testing::api().stat)(path.into()).into()
+    }
+
+    fn set_attributes(path: &str, attr: Attributes) {
+        Result::from((testing::api().set_attributes)(path.into(), attr)).unwrap();
+    }
+
+    #[test]
+    fn move_on_same_drive() {
+        testing::reset(&["HD0:/A.TXT", "HD0:/DOCS/", "HD1:/"]);
+        move_path("HD0:/A.TXT", "DOCS/B.TXT").unwrap();
+        assert_eq!(
+            testing::paths(),
+            ["HD0:", "HD0:/DOCS", "HD0:/DOCS/B.TXT", "HD1:"]
+        );
+    }
+
+    #[test]
+    fn move_file_to_other_drive() {
+        testing::reset(&["HD0:/A.TXT", "HD1:/DOCS/"]);
+        set_attributes("HD0:/A.TXT", Attributes::READ_ONLY);
+        let before = stat("HD0:/A.TXT").unwrap();
+        move_path("HD0:/A.TXT", "HD1:/DOCS/B.TXT").unwrap();
+        assert_eq!(
+            testing::paths(),
+            ["HD0:", "HD1:", "HD1:/DOCS", "HD1:/DOCS/B.TXT"]
+        );
+        let after = stat("HD1:/DOCS/B.TXT").unwrap();
+        assert_eq!(after.attr, Attributes::READ_ONLY);
+        assert_eq!(after.mtime, before.mtime);
+    }
+
+    #[test]
+    fn move_tree_to_other_drive() {
+        testing::reset(&[
+            "HD0:/SRC/A.TXT",
+            "HD0:/SRC/SUB/B.TXT",
+            "HD0:/SRC/EMPTY/",
+            "HD1:/",
+        ]);
+        move_path("HD0:/SRC/", "HD1:/DST").unwrap();
+        assert_eq!(
+            testing::paths(),
+            [
+                "HD0:",
+                "HD1:",
+                "HD1:/DST",
+                "HD1:/DST/A.TXT",
+                "HD1:/DST/EMPTY",
+                "HD1:/DST/SUB",
+                "HD1:/DST/SUB/B.TXT",
+            ]
+        );
+        assert_eq!(testing::open_handles(), 0);
+    }
+
+    #[test]
+    fn move_tree_keeps_metadata() {
+        testing::reset(&["HD0:/SRC/SUB/A.TXT", "HD1:/"]);
+        let api = testing::api();
+        let old = Time::new(2001, 2, 3, 4, 5, 6).unwrap();
+        let older = Time::new(2000, 1, 1, 0, 0, 0).unwrap();
+        for path in ["HD0:/SRC", "HD0:/SRC/SUB", "HD0:/SRC/SUB/A.TXT"] {
+            Result::from((api.set_times)(path.into(), older, old)).unwrap();
+        }
+        set_attributes("HD0:/SRC", Attributes::SYSTEM);
+        set_attributes("HD0:/SRC/SUB", Attributes::HIDDEN | Attributes::READ_ONLY);
+        move_path("HD0:/SRC", "HD1:/DST").unwrap();
+        for (path, attr) in [
+            ("HD1:/DST", Attributes::DIRECTORY | Attributes::SYSTEM),
+            (
+                "HD1:/DST/SUB",
+                Attributes::DIRECTORY | Attributes::HIDDEN | Attributes::READ_ONLY,
+            ),
+            ("HD1:/DST/SUB/A.TXT", Attributes::ARCHIVE),
+        ] {
+            let stat = stat(path).unwrap();
+            assert_eq!(stat.attr, attr, "{path}");
+            assert_eq!(stat.ctime, older, "{path}");
+            assert_eq!(stat.mtime, old, "{path}");
+        }
+    }
+
+    #[test]
+    fn set_attributes_and_times() {
+        testing::reset(&["HD0:/A.TXT"]);
+        let api = testing::api();
+        set_attributes("HD0:/A.TXT", Attributes::DIRECTORY | Attributes::HIDDEN);
+        assert_eq!(stat("HD0:/A.TXT").unwrap().attr, Attributes::HIDDEN);
+        let mut bad = Time::new(2001, 2, 3, 4, 5, 6).unwrap();
+        bad.hours = 24;
+        let good = Time::new(2001, 2, 3, 4, 5, 6).unwrap();
+        assert_eq!(
+            Result::from((api.set_times)("HD0:/A.TXT".into(), good, bad)),
+            Err(Error::InvalidArg)
+        );
+        assert_eq!(
+            Result::from((api.set_times)("HD0:/B.TXT".into(), good, good)),
+            Err(Error::NotFound)
+        );
+    }
+
+    #[test]
+    fn move_rolls_back() {
+        let tree = [
+            "HD0:/SRC/A.TXT",
+            "HD0:/SRC/SUB/B.TXT",
+            "HD0:/SRC/SUB/C.TXT",
+            "HD1:/",
+        ];
+        testing::reset(&tree);
+        testing::set_broken("HD0:/SRC/SUB/C.TXT");
+        let before = testing::paths();
+        assert_eq!(
+            move_path("HD0:/SRC", "HD1:/DST"),
+            Err(Error::DeviceSpecific)
+        );
+        assert_eq!(testing::paths(), before);
+        assert_eq!(testing::open_handles(), 0);
+    }
+
+    #[test]
+    fn remove_dir_all_relative() {
+        testing::reset(&[
+            "HD0:/OLD/A.TXT",
+            "HD0:/OLD/SUB/B.TXT",
+            "HD0:/OLD/SUB/DEEP/",
+            "HD0:/KEEP",
+        ]);
+        Result::from((testing::api().chdir)("HD0:/OLD/SUB".into())).unwrap();
+        remove_dir_all::<64>(&testing::api(), "..", false).unwrap();
+        assert_eq!(testing::paths(), ["HD0:", "HD0:/KEEP"]);
+        assert_eq!(testing::open_handles(), 0);
+    }
+
+    #[test]
+    fn remove_tree_read_only() {
+        testing::reset(&["HD0:/OLD/A.TXT", "HD0:/OLD/SUB/B.TXT"]);
+        set_attributes("HD0:/OLD/SUB/B.TXT", Attributes::READ_ONLY);
+        let e = remove_dir_all::<64>(&testing::api(), "HD0:/OLD", false).unwrap_err();
+        assert_eq!(e.path.unwrap().as_str(), "HD0:/OLD/SUB/B.TXT");
+        assert_eq!(e.error, Error::FileReadOnly);
+        assert_eq!(
+            testing::paths(),
+            ["HD0:", "HD0:/OLD", "HD0:/OLD/SUB", "HD0:/OLD/SUB/B.TXT"]
+        );
+        // The OS won't delete read-only things itself
+        assert_eq!(
+            Result::from((testing::api().deletefile)("HD0:/OLD/SUB/B.TXT".into())),
+            Err(Error::FileReadOnly)
+        );
+        set_attributes("HD0:/OLD/SUB", Attributes::READ_ONLY);
+        set_attributes("HD0:/OLD", Attributes::READ_ONLY | Attributes::HIDDEN);
+        let e = remove_dir_all::<64>(&testing::api(), "HD0:/OLD", false).unwrap_err();
+        assert_eq!(e.path.unwrap().as_str(), "HD0:/OLD");
+        assert_eq!(e.error, Error::FileReadOnly);
+        remove_dir_all::<64>(&testing::api(), "HD0:/OLD", true).unwrap();
+        assert_eq!(testing::paths(), ["HD0:"]);
+    }
+
+    #[test]
+    fn remove_tree_errors() {
+        testing::reset(&["HD0:/OLD/A.TXT", "HD0:/OLD/SUB/B.TXT", "HD0:/FILE.TXT"]);
+        testing::set_broken("HD0:/OLD/SUB");
+        let e = remove_dir_all::<64>(&testing::api(), "HD0:/OLD", true).unwrap_err();
+        assert_eq!(e.path.as_ref().unwrap().as_str(), "HD0:/OLD/SUB");
+        assert_eq!(e.error, Error::DeviceSpecific);
+        assert_eq!(
+            std::format!("{}", e),
+            "cannot remove HD0:/OLD/SUB: DeviceSpecific"
+        );
+        let e = remove_dir_all::<64>(&testing::api(), "HD0:/NEW", false).unwrap_err();
+        assert_eq!(e.error, Error::NotFound);
+        let e = remove_dir_all::<64>(&testing::api(), "HD0:/FILE.TXT", false).unwrap_err();
+        assert_eq!(e.error, Error::InvalidPath);
+        assert_eq!(Error::from(e), Error::InvalidPath);
+        assert_eq!(testing::open_handles(), 0);
+    }
+
+    #[test]
+    fn temp_files() {
+        testing::reset(&["HD0:/TEMP/~T000001.TMP"]);
+        let api = testing::api();
+        // No other test makes temporary files, so nothing else moves this
+        NEXT_TEMP_FILE.store(0, Ordering::Relaxed);
+        let first = TempFile::<64>::create(&api, "HD0:/TEMP", false).unwrap();
+        assert_eq!(first.path().as_str(), "HD0:/TEMP/~T000000.TMP");
+        Result::from((api.chdir)("HD0:/TEMP".into())).unwrap();
+        let second = TempFile::<64>::create(&api, ".", true).unwrap();
+        assert_eq!(second.path().as_str(), "HD0:/TEMP/~T000002.TMP");
+        assert_ne!(first.handle(), second.handle());
+        assert_eq!(testing::open_handles(), 2);
+        first.close().unwrap();
+        drop(second);
+        assert_eq!(testing::open_handles(), 0);
+        assert_eq!(
+            testing::paths(),
+            [
+                "HD0:",
+                "HD0:/TEMP",
+                "HD0:/TEMP/~T000000.TMP",
+                "HD0:/TEMP/~T000001.TMP"
+            ]
+        );
+        let third = TempFile::<64>::create(&api, "HD0:/TEMP", true).unwrap();
+        let handle = third.into_handle();
+        assert_eq!(testing::open_handles(), 1);
+        Result::from((api.close)(handle)).unwrap();
+        assert_eq!(testing::paths().len(), 4);
+        assert!(TempFile::<64>::create(&api, "HD0:/NONE", true).is_err());
+        // The search carries on from the last name, and wraps around
+        let fourth = TempFile::<64>::create(&api, "HD0:/TEMP", true).unwrap();
+        assert_eq!(fourth.path().as_str(), "HD0:/TEMP/~T000004.TMP");
+        drop(fourth);
+        NEXT_TEMP_FILE.store(MAX_TEMP_FILES - 1, Ordering::Relaxed);
+        let last = TempFile::<64>::create(&api, "HD0:/TEMP", true).unwrap();
+        assert_eq!(last.path().as_str(), "HD0:/TEMP/~T999999.TMP");
+        drop(last);
+        let wrapped = TempFile::<64>::create(&api, "HD0:/TEMP", true).unwrap();
+        assert_eq!(wrapped.path().as_str(), "HD0:/TEMP/~T000002.TMP");
+    }
+
+    #[test]
+    fn move_too_deep() {
+        let mut deep = std::string::String::from("HD0:/SRC/");
+        for _ in 0..MAX_WALK_DEPTH {
+            deep.push_str("D/");
+        }
+        let file = deep.clone() + "X.TXT";
+        testing::reset(&[&file, "HD1:/"]);
+        let before = testing::paths();
+        assert_eq!(move_path("HD0:/SRC", "HD1:/DST"), Err(Error::InvalidArg));
+        assert_eq!(testing::paths(), before);
+        deep.truncate(deep.len() - 2);
+        testing::reset(&[&(deep + "X.TXT"), "HD1:/"]);
+        move_path("HD0:/SRC", "HD1:/DST").unwrap();
+        assert_eq!(testing::paths().last().unwrap().matches("D/").count(), 15);
+    }
+
+    #[test]
+    fn move_refuses_to_overwrite() {
+        testing::reset(&["HD0:/A.TXT", "HD0:/DIR/", "HD1:/A.TXT", "HD1:/DIR/X.TXT"]);
+        let before = testing::paths();
+        assert_eq!(
+            move_path("HD0:/A.TXT", "HD1:/A.TXT"),
+            Err(Error::AlreadyExists)
+        );
+        assert_eq!(move_path("HD0:/DIR", "HD1:/DIR"), Err(Error::AlreadyExists));
+        assert_eq!(move_path("HD0:/B.TXT", "HD1:/B.TXT"), Err(Error::NotFound));
+        assert_eq!(testing::paths(), before);
+    }
+
+    #[test]
+    fn remove_dir_all_leaves_files_alone() {
+        testing::reset(&["HD0:/FILE.TXT"]);
+        set_attributes("HD0:/FILE.TXT", Attributes::READ_ONLY);
+        let e = remove_dir_all::<64>(&testing::api(), "HD0:/FILE.TXT", true).unwrap_err();
+        assert_eq!(e.path.unwrap().as_str(), "HD0:/FILE.TXT");
+        assert_eq!(e.error, Error::InvalidPath);
+        // Still there, and still read-only
+        assert_eq!(stat("HD0:/FILE.TXT").unwrap().attr, Attributes::READ_ONLY);
+    }
+
+    #[test]
+    fn remove_dir_all_refuses_drive_root() {
+        testing::reset(&["HD0:/A.TXT", "HD0:/SUB/B.TXT"]);
+        let before = testing::paths();
+        for root in ["HD0:/", "HD0:"] {
+            let e = remove_dir_all::<64>(&testing::api(), root, true).unwrap_err();
+            assert_eq!(e.error, Error::InvalidPath);
+        }
+        // Relative paths which resolve to the root are refused too
+        let e = remove_dir_all::<64>(&testing::api(), "SUB/..", true).unwrap_err();
+        assert_eq!(e.path.unwrap().as_str(), "HD0:/");
+        assert_eq!(e.error, Error::InvalidPath);
+        assert_eq!(testing::paths(), before);
+    }
+}
+
+// ============================================================================
+// End of File
+// ============================================================================
@@ -10,11 +10,19 @@
 // Imports
 // ============================================================================
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod dir;
+pub mod drive;
 pub mod file;
+pub mod fs;
 pub mod path;
 
-pub use neotron_ffi::{FfiBuffer, FfiByteSlice, FfiString};
+#[cfg(test)]
+mod testing;
+
+pub use neotron_ffi::{FfiBuffer, FfiByteSlice, FfiOption, FfiString};
 
 // ============================================================================
 // Constants
@@ -22,7 +30,25 @@ pub use neotron_ffi::{FfiBuffer, FfiByteSlice, FfiString};
 
 /// Maximum length of a filename (with no directory components), including the
 /// extension.
-pub const MAX_FILENAME_LEN: usize = 11;
+///
+/// This is an 8.3 name - eight characters, a `.`, then three characters.
+///
+/// This was 11 in v0.2.0, which has no room for the `.`. It sets the size of
+/// [`dir::Entry`], so changing it changes the ABI.
+pub const MAX_FILENAME_LEN: usize = 12;
+
+/// Maximum length of a drive name (e.g. `HD0`), not including the `:`.
+pub const MAX_DRIVE_NAME_LEN: usize = 8;
+
+/// Maximum length of a volume label.
+pub const MAX_VOLUME_LABEL_LEN: usize = 11;
+
+/// Maximum length of a long filename, in bytes of UTF-8.
+///
+/// A VFAT long filename is up to 255 UTF-16 code units, and each of those
+/// takes at most three bytes when encoded as UTF-8. A buffer of this size is
+/// therefore always big enough for [`Api::readdir_long`].
+pub const MAX_LONG_FILENAME_LEN: usize = 255 * 3;
 
 // ============================================================================
 // Types
@@ -38,14 +64,21 @@ pub type Result<T> = neotron_ffi::FfiResult<T, Error>;
 pub struct Api {
     /// Open a file, given a path as UTF-8 string.
     ///
-    /// If the file does not exist, or is already open, it returns an error.
+    /// If the file does not exist (and `flags` do not ask for it to be
+    /// created), it returns an error.
     ///
     /// Path may be relative to current directory, or it may be an absolute
     /// path.
     ///
+    /// A file which is already open can be opened again. Each handle has its
+    /// own position in the file, and data written through one handle can be
+    /// read through the others. The handles do not stop each other reading or
+    /// writing - use `Api::lock` to agree who may change which bytes.
+    ///
     /// # Limitations
     ///
-    /// * You cannot open a file if it is currently open.
+    /// * You cannot open a file with `file::Flags::TRUNCATE` while it is open
+    ///   on another handle - you get `Err(Error::InvalidArg)`.
     /// * Paths must confirm to the rules for the filesystem for the given drive.
     /// * Relative paths are taken relative to the current directory (see `Api::chdir`).
     pub open: extern "C" fn(path: FfiString, flags: file::Flags) -> Result<file::Handle>,
@@ -105,6 +138,9 @@ pub struct Api {
     /// Close a previously opened directory.
     pub closedir: extern "C" fn(dir: dir::Handle) -> Result<()>,
     /// Read from an open directory
+    ///
+    /// If you do an `Api::readdir` and you are already at the end of the
+    /// directory, you will get `Err(Error::EndOfFile)`.
     pub readdir: extern "C" fn(dir: dir::Handle) -> Result<dir::Entry>,
     /// Get information about a file.
     pub stat: extern "C" fn(path: FfiString) -> Result<file::Stat>,
@@ -115,6 +151,8 @@ pub struct Api {
     /// # Limitations
     ///
     /// * You cannot delete a file if it is currently open.
+    /// * You cannot delete a read-only file - you get
+    ///   `Err(Error::FileReadOnly)`. Clear its `READ_ONLY` attribute first.
     pub deletefile: extern "C" fn(path: FfiString) -> Result<()>,
     /// Delete a directory.
     ///
@@ -122,6 +160,8 @@ pub struct Api {
     ///
     /// * You cannot delete a root directory.
     /// * You cannot delete a directory that has any files or directories in it.
+    /// * You cannot delete a read-only directory - you get
+    ///   `Err(Error::FileReadOnly)`. Clear its `READ_ONLY` attribute first.
     pub deletedir: extern "C" fn(path: FfiString) -> Result<()>,
     /// Change the current directory.
     ///
@@ -152,6 +192,226 @@ pub struct Api {
     ///
     /// You must pass the same `size` and `alignment` values that you passed to `malloc`.
     pub free: extern "C" fn(ptr: *mut core::ffi::c_void, size: usize, alignment: usize),
+    /// Get the system's time zone.
+    ///
+    /// All [`file::Time`] values passed over this API are in this time zone.
+    pub get_timezone: extern "C" fn() -> Result<file::TimeZone>,
+    /// Set the system's time zone.
+    ///
+    /// This does not change the system clock's idea of UTC, so the local time
+    /// will jump by the difference between the old and new offsets.
+    pub set_timezone: extern "C" fn(tz: file::TimeZone) -> Result<()>,
+    /// Read from an open directory, including the entry's long filename.
+    ///
+    /// This works like `Api::readdir`, but the entry's long filename is also
+    /// stored as UTF-8 into the given buffer. The returned
+    /// [`dir::LongEntry`] says how many bytes were written. If the entry only
+    /// has a short (8.3) name, zero bytes are written.
+    ///
+    /// If the long filename does not fit in the buffer, you get
+    /// `Err(Error::InvalidArg)` and the directory position does not move, so
+    /// you can try again with a bigger buffer. A buffer of
+    /// [`MAX_LONG_FILENAME_LEN`] bytes is always big enough.
+    pub readdir_long:
+        extern "C" fn(dir: dir::Handle, long_name: FfiBuffer) -> Result<dir::LongEntry>,
+    /// Get the name of a drive.
+    ///
+    /// Drives are numbered from zero. The name of the drive with the given
+    /// `index` is stored as UTF-8 into the given buffer, without the trailing
+    /// `:` (e.g. `HD0`), and the function returns the number of bytes written.
+    ///
+    /// To list every drive, call this with an `index` of `0`, then `1`, and so
+    /// on, until you get `Err(Error::EndOfFile)`. The numbering may change if
+    /// drives are added or removed.
+    ///
+    /// If the name does not fit in the buffer, you get
+    /// `Err(Error::InvalidArg)`. A buffer of [`MAX_DRIVE_NAME_LEN`] bytes is
+    /// always big enough.
+    pub list_drives: extern "C" fn(index: usize, name: FfiBuffer) -> Result<usize>,
+    /// Get information about the volume on a drive.
+    ///
+    /// The `drive` is a drive name like `HD0`, with or without a trailing `:`.
+    pub volume_info: extern "C" fn(drive: FfiString) -> Result<drive::VolumeInfo>,
+    /// Mount a volume as a drive.
+    ///
+    /// * `device` is either the name of a block device (as reported by the
+    ///   BIOS), or the path of a disk image file to mount.
+    /// * `drive` is the name for the new drive, like `SD0`, with or without a
+    ///   trailing `:`.
+    /// * `fs_type` is the kind of filesystem on the volume. Pass
+    ///   `FsType::Auto` to have the OS work it out. `FsType::Unknown` and
+    ///   `FsType::Device` are only ever reported, so passing them gives
+    ///   `Err(Error::InvalidArg)`.
+    ///
+    /// If the device or disk image does not exist, you get
+    /// `Err(Error::NotFound)`.
+    ///
+    /// # Limitations
+    ///
+    /// * You cannot use a drive name which is already in use, by a drive or
+    ///   an alias. You get `Err(Error::AlreadyExists)`.
+    /// * You cannot mount a disk image file which is currently open. You get
+    ///   `Err(Error::InvalidArg)`.
+    pub mount:
+        extern "C" fn(device: FfiString, drive: FfiString, fs_type: drive::FsType) -> Result<()>,
+    /// Unmount a drive.
+    ///
+    /// Any cached data is written out to the volume first, so it is then safe
+    /// to remove (e.g. to swap the SD card).
+    ///
+    /// If there is no such drive, you get `Err(Error::NotFound)`.
+    ///
+    /// # Limitations
+    ///
+    /// * You cannot unmount a drive which has open files or directories.
+    /// * You cannot unmount a drive which an alias refers to (see
+    ///   `Api::assign`).
+    ///
+    /// In both cases you get `Err(Error::InvalidArg)`.
+    pub unmount: extern "C" fn(drive: FfiString) -> Result<()>,
+    /// Create an alias for a directory, which can then be used as a drive.
+    ///
+    /// For example, if `SYS` is assigned to `HD0:/NEOTRON/`, then the path
+    /// `SYS:/CONFIG.TXT` refers to `HD0:/NEOTRON/CONFIG.TXT`. The OS resolves
+    /// aliases, so they work with every function that takes a path.
+    ///
+    /// * `alias` is the name of the alias, like `SYS`, with or without a
+    ///   trailing `:`.
+    /// * `target` is the absolute path of a directory. It may itself use an
+    ///   alias.
+    ///
+    /// If the alias already exists, it is changed to refer to the new target.
+    /// If the target is not a directory, you get `Err(Error::NotFound)` (or
+    /// `Err(Error::InvalidPath)` if it is a file or a relative path).
+    ///
+    /// # Limitations
+    ///
+    /// * You cannot use the name of a mounted drive as an alias. You get
+    ///   `Err(Error::AlreadyExists)`.
+    pub assign: extern "C" fn(alias: FfiString, target: FfiString) -> Result<()>,
+    /// Remove an alias created with `Api::assign`.
+    ///
+    /// If there is no such alias, you get `Err(Error::NotFound)`.
+    pub unassign: extern "C" fn(alias: FfiString) -> Result<()>,
+    /// Go back to the start of an open directory.
+    ///
+    /// The next `Api::readdir` will return the first entry again.
+    pub rewinddir: extern "C" fn(dir: dir::Handle) -> Result<()>,
+    /// Get the current position within an open directory.
+    ///
+    /// The position can be passed to `Api::seekdir` to come back to this
+    /// point later.
+    pub telldir: extern "C" fn(dir: dir::Handle) -> Result<dir::Position>,
+    /// Move to a position within an open directory.
+    ///
+    /// The `position` must have come from `Api::telldir` on the same open
+    /// handle. Positions are not valid once the handle is closed, even if the
+    /// same directory is opened again.
+    pub seekdir: extern "C" fn(dir: dir::Handle, position: dir::Position) -> Result<()>,
+    /// Read many entries from an open directory at once.
+    ///
+    /// This works like calling `Api::readdir` repeatedly, storing each entry
+    /// into the given buffer in turn. It returns the number of entries
+    /// written, which is less than the length of the buffer only if the end
+    /// of the directory was reached.
+    ///
+    /// If you are already at the end of the directory, you will get
+    /// `Err(Error::EndOfFile)`. An empty buffer gives `Err(Error::InvalidArg)`.
+    pub readdir_many: extern "C" fn(dir: dir::Handle, buffer: dir::EntryBuffer) -> Result<usize>,
+    /// Copy a file.
+    ///
+    /// The OS copies the data itself, so the application does not need a
+    /// buffer. Returns the number of bytes copied.
+    ///
+    /// The `flags` say whether an existing `new_path` may be replaced, and
+    /// whether the attributes and timestamps of `old_path` are given to
+    /// `new_path`. If `new_path` exists and `file::CopyFlags::OVERWRITE` is
+    /// not set, you get `Err(Error::AlreadyExists)`.
+    ///
+    /// # Limitations
+    ///
+    /// * You cannot copy a directory.
+    /// * You cannot copy a file to or from a file which is currently open.
+    /// * Paths must confirm to the rules for the filesystem for the given drive.
+    pub copy_file: extern "C" fn(
+        old_path: FfiString,
+        new_path: FfiString,
+        flags: file::CopyFlags,
+    ) -> Result<u64>,
+    /// Copy data from one open file to another.
+    ///
+    /// Up to `length` bytes are read from the current position in `src` and
+    /// written at the current position in `dst`, and both positions move
+    /// forward. Copying stops early at the end of `src`, so pass `u64::MAX`
+    /// to copy everything that is left. Returns the number of bytes copied.
+    ///
+    /// `src` must have been opened for reading, and `dst` with
+    /// `file::Flags::WRITE`. The OS copies the data itself, so the application
+    /// does not need a buffer.
+    pub splice: extern "C" fn(src: file::Handle, dst: file::Handle, length: u64) -> Result<u64>,
+    /// Create a new, empty, directory.
+    ///
+    /// Path may be relative to current directory, or it may be an absolute
+    /// path. A trailing `/` is allowed. The new directory has no attributes
+    /// set (apart from `file::Attributes::DIRECTORY`) and is timestamped
+    /// with the current time.
+    ///
+    /// * If something (a file or a directory) already exists at `path`, you
+    ///   get `Err(Error::AlreadyExists)`.
+    /// * If the parent directory does not exist, you get
+    ///   `Err(Error::NotFound)`.
+    ///
+    /// # Limitations
+    ///
+    /// * Only one directory is created at a time, so the parent directory
+    ///   must already exist.
+    /// * Paths must confirm to the rules for the filesystem for the given drive.
+    pub mkdir: extern "C" fn(path: FfiString) -> Result<()>,
+    /// Lock some bytes in an open file.
+    ///
+    /// If another handle holds a lock which conflicts (see
+    /// [`file::LockKind`]), this waits until that lock is released. Locks are
+    /// advisory - they only affect other calls to lock the file, not reading
+    /// or writing it.
+    ///
+    /// Locking a range this handle has already locked replaces the old lock,
+    /// so you can change a shared lock into an exclusive one. All of a
+    /// handle's locks are released when the file is closed.
+    pub lock:
+        extern "C" fn(fd: file::Handle, kind: file::LockKind, range: file::LockRange) -> Result<()>,
+    /// Try to lock some bytes in an open file, without waiting.
+    ///
+    /// This works like `Api::lock`, except that if another handle holds a lock
+    /// which conflicts, you get `Err(Error::WouldBlock)` straight away.
+    pub try_lock:
+        extern "C" fn(fd: file::Handle, kind: file::LockKind, range: file::LockRange) -> Result<()>,
+    /// Release the locks this handle holds on some bytes in an open file.
+    ///
+    /// Locks which only partly overlap `range` are shortened. Unlocking bytes
+    /// which are not locked is not an error.
+    pub unlock: extern "C" fn(fd: file::Handle, range: file::LockRange) -> Result<()>,
+    /// Change the attributes of a file or directory.
+    ///
+    /// Only `READ_ONLY`, `HIDDEN`, `SYSTEM` and `ARCHIVE` can be changed. Any
+    /// other bits in `attr` are ignored, and the file or directory keeps
+    /// whatever it had before.
+    ///
+    /// # Limitations
+    ///
+    /// * You cannot change the attributes of a file if it is currently open.
+    pub set_attributes: extern "C" fn(path: FfiString, attr: file::Attributes) -> Result<()>,
+    /// Change the creation and modification times of a file or directory.
+    ///
+    /// If either time is not valid (see `file::Time::is_valid`), you get
+    /// `Err(Error::InvalidArg)`.
+    ///
+    /// # Limitations
+    ///
+    /// * You cannot change the times of a file if it is currently open.
+    /// * FAT only stores times to the nearest two seconds, so odd seconds may
+    ///   be rounded down.
+    pub set_times:
+        extern "C" fn(path: FfiString, ctime: file::Time, mtime: file::Time) -> Result<()>,
 }
 
 /// The type of the entry function for an application
@@ -189,6 +449,10 @@ pub enum Error {
     OutOfMemory,
     /// The given path was invalid
     InvalidPath,
+    /// The given file/directory path already exists
+    AlreadyExists,
+    /// The operation could not be done without waiting
+    WouldBlock,
 }
 
 // ============================================================================
@@ -58,6 +58,12 @@
 /// Files and Directories generally have distinct APIs, so a directory without a
 /// trailing `/` is likely to be accepted. A file path with a trailing `/` won't
 /// be accepted.
+///
+/// Comparing and hashing a `Path` is byte-exact, so `hd0:/foo.txt` and
+/// `HD0:/FOO.TXT` are different. See [`Path::case_insensitive`] and
+/// [`CaseFolding`] if you want to compare paths the way a FAT filesystem
+/// would.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Path<'a>(&'a str);
 
 impl<'a> Path<'a> {
@@ -182,28 +188,1274 @@ impl<'a> Path<'a> {
     ///
     /// A path like `DS0:/FOO/BAR.TXT` has a filename extension portion of `TXT`.
     /// A path like `DS0:/FOO/BAR` has no filename extension portion.
+    /// A path like `DS0:/FOO/.BAR` has no filename extension portion either -
+    /// leading `.`s do not start an extension.
     pub fn extension(&self) -> Option<&str> {
         let Some(filename) = self.filename() else {
             return None;
         };
-        if let Some((_basename, extension)) = filename.rsplit_once('.') {
-            Some(extension)
-        } else {
-            None
+        split_extension(filename).1
+    }
+
+    /// Get the filename portion of this path, without the extension.
+    ///
+    /// * A path like `DS0:/FOO/BAR.TXT` has a file stem of `BAR`.
+    /// * A path like `DS0:/FOO/BAR` has a file stem of `BAR`.
+    /// * A path like `DS0:/FOO/.PROFILE` has a file stem of `.PROFILE` -
+    ///   leading `.`s do not start an extension.
+    /// * A path like `DS0:/FOO/` has no file stem.
+    pub fn file_stem(&self) -> Option<&str> {
+        let filename = self.filename()?;
+        Some(split_extension(filename).0)
+    }
+
+    /// Get the path of the directory containing this path.
+    ///
+    /// The parent always has a trailing `/`, as it is a directory.
+    ///
+    /// * A path like `DS0:/FOO/BAR.TXT` has a parent of `DS0:/FOO/`.
+    /// * A path like `DS0:/FOO/` has a parent of `DS0:/`.
+    /// * A path like `FOO/BAR` has a parent of `FOO/`.
+    /// * Paths like `DS0:/`, `DS0:` and `BAR.TXT` have no parent.
+    pub fn parent(&self) -> Option<Path<'a>> {
+        let (drive_specifier_len, drive_path) = split_drive(self.0)?;
+        let trimmed = drive_path
+            .strip_suffix(Self::PATH_SEP)
+            .unwrap_or(drive_path);
+        let idx = trimmed.rfind(Self::PATH_SEP)?;
+        Some(Path(&self.0[..drive_specifier_len + idx + 1]))
+    }
+
+    /// Iterate through this path, and then each of its parents in turn.
+    ///
+    /// So `DS0:/FOO/BAR.TXT` produces `DS0:/FOO/BAR.TXT`, `DS0:/FOO/` and then
+    /// `DS0:/`.
+    pub fn ancestors(&self) -> Ancestors<'a> {
+        Ancestors { next: Some(*self) }
+    }
+
+    /// Iterate through the components of this path.
+    ///
+    /// So `DS0:/FOO/BAR.TXT` produces `Drive("DS0")`, `RootDir`,
+    /// `Normal("FOO")` and then `Normal("BAR.TXT")`. Repeated `/` separators
+    /// are ignored.
+    pub fn components(&self) -> Components<'a> {
+        let (drive, root, rest) = match self.0.split_once(Self::DRIVE_SEP) {
+            Some((drive, drive_path)) => (
+                Some(drive),
+                true,
+                drive_path.trim_start_matches(Self::PATH_SEP),
+            ),
+            None => (None, false, self.0),
+        };
+        Components { drive, root, rest }
+    }
+
+    /// Lexically normalise this path into an owned path.
+    ///
+    /// * `.` components are removed.
+    /// * `..` components remove the component before them.
+    /// * Repeated `/` separators are collapsed into one.
+    /// * A bare drive like `HD0:` becomes `HD0:/`.
+    ///
+    /// So `HD0:/A/./B/../C.TXT` becomes `HD0:/A/C.TXT`. This does not look at
+    /// the filesystem, so it doesn't know about aliases or links.
+    ///
+    /// Any leading `..` components in a relative path are kept, as they refer
+    /// to something outside of this path. A relative path which normalises to
+    /// nothing (like `A/..`) becomes `./`.
+    ///
+    /// If a `..` would climb above the root of a drive, you get
+    /// `Err(Error::InvalidPath)`. If the result is longer than `N` bytes, you
+    /// get `Err(Error::InvalidArg)`.
+    pub fn normalise<const N: usize>(&self) -> Result<PathBuf<N>, crate::Error> {
+        let mut output = PathBuf::empty();
+        normalise_into(self, &mut output)?;
+        Ok(output)
+    }
+
+    /// Lexically normalise this path into an owned path on the heap.
+    ///
+    /// Only available with the `alloc` feature. See [`Path::normalise`].
+    #[cfg(feature = "alloc")]
+    pub fn normalise_alloc(&self) -> Result<HeapPathBuf, crate::Error> {
+        let mut output = HeapPathBuf(alloc::string::String::new());
+        normalise_into(self, &mut output)?;
+        Ok(output)
+    }
+
+    /// Turn this path into a normalised absolute path, using the given current
+    /// directory.
+    ///
+    /// If this path is absolute, this is the same as [`Path::normalise`].
+    /// Otherwise this path is taken to be relative to `cwd`, which must be an
+    /// absolute path (or you get `Err(Error::InvalidPath)`).
+    ///
+    /// So `../SALES.TXT` in `HD0:/DOCS/JUNE` becomes `HD0:/DOCS/SALES.TXT`.
+    ///
+    /// If the result is longer than `N` bytes, you get
+    /// `Err(Error::InvalidArg)`.
+    pub fn resolve<const N: usize>(&self, cwd: &Path) -> Result<PathBuf<N>, crate::Error> {
+        let mut output = PathBuf::empty();
+        resolve_into(self, cwd, &mut output)?;
+        Ok(output)
+    }
+
+    /// Turn this path into a normalised absolute path on the heap, using the
+    /// given current directory.
+    ///
+    /// Only available with the `alloc` feature. See [`Path::resolve`].
+    #[cfg(feature = "alloc")]
+    pub fn resolve_alloc(&self, cwd: &Path) -> Result<HeapPathBuf, crate::Error> {
+        let mut output = HeapPathBuf(alloc::string::String::new());
+        resolve_into(self, cwd, &mut output)?;
+        Ok(output)
+    }
+
+    /// Turn this path into a normalised absolute path, using the system's
+    /// current directory.
+    ///
+    /// The current directory is fetched with [`Api::pwd`](crate::Api::pwd),
+    /// and must fit in `N` bytes. See [`Path::resolve`] for details.
+    pub fn absolute<const N: usize>(&self, api: &crate::Api) -> Result<PathBuf<N>, crate::Error> {
+        if self.is_absolute_path() {
+            return self.normalise();
+        }
+        let mut buffer = [0u8; N];
+        let len: usize = Result::from((api.pwd)(crate::FfiBuffer::new(&mut buffer)))?;
+        let cwd = buffer
+            .get(..len)
+            .and_then(|bytes| core::str::from_utf8(bytes).ok())
+            .ok_or(crate::Error::InvalidPath)?;
+        self.resolve(&Path::new(cwd)?)
+    }
+
+    /// Compare this path with another, using the given case folding rules.
+    pub fn eq_with(&self, other: &Path, folding: CaseFolding) -> bool {
+        self.cmp_with(other, folding) == core::cmp::Ordering::Equal
+    }
+
+    /// Order this path against another, using the given case folding rules.
+    pub fn cmp_with(&self, other: &Path, folding: CaseFolding) -> core::cmp::Ordering {
+        match folding {
+            CaseFolding::Exact => self.0.cmp(other.0),
+            CaseFolding::Ascii => {
+                let this = self.0.bytes().map(|b| b.to_ascii_uppercase());
+                let that = other.0.bytes().map(|b| b.to_ascii_uppercase());
+                this.cmp(that)
+            }
+        }
+    }
+
+    /// Hash this path, using the given case folding rules.
+    ///
+    /// Two paths which are equal according to [`Path::eq_with`] (with the
+    /// same `folding`) will have the same hash.
+    pub fn hash_with<H: core::hash::Hasher>(&self, state: &mut H, folding: CaseFolding) {
+        match folding {
+            CaseFolding::Exact => core::hash::Hash::hash(self.0, state),
+            CaseFolding::Ascii => {
+                for b in self.0.bytes() {
+                    state.write_u8(b.to_ascii_uppercase());
+                }
+                // Match what `str` does, so `("a", "b")` and `("ab", "")`
+                // hash differently
+                state.write_u8(0xFF);
+            }
         }
     }
 
+    /// Check this path against the naming rules of a particular filesystem.
+    ///
+    /// [`Path::new`] only applies the rules common to every filesystem. Use
+    /// this to find out whether a path will be accepted by a particular type
+    /// of filesystem, before you pass it to the OS.
+    ///
+    /// Drive specifiers, `.` and `..` are not checked. If a name is not
+    /// valid, the error tells you which one, and why.
+    pub fn validate(&self, profile: Profile) -> Result<(), ValidationError<'a>> {
+        for (index, component) in self.components().enumerate() {
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            let result = match profile {
+                Profile::Generic => Ok(()),
+                Profile::Fat83 => validate_fat83(name),
+                Profile::Vfat => validate_vfat(name),
+            };
+            result.map_err(|reason| ValidationError {
+                component: name,
+                index,
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Wrap this path so it compares and hashes ignoring ASCII case.
+    ///
+    /// This is how paths on FAT volumes behave, so `hd0:/foo.txt` and
+    /// `HD0:/FOO.TXT` will be equal. Useful as a key in a map or set.
+    pub fn case_insensitive(self) -> CaseInsensitivePath<'a> {
+        CaseInsensitivePath(self)
+    }
+
     /// View this [`Path`] as a string-slice.
     pub fn as_str(&self) -> &str {
         self.0
     }
+
+    /// View this [`Path`] as an [`FfiString`](crate::FfiString), ready to
+    /// pass to an [`Api`](crate::Api) function like `open` or `stat`.
+    ///
+    /// This does not copy the path.
+    pub fn as_ffi_string(&self) -> crate::FfiString<'a> {
+        crate::FfiString::new(self.0)
+    }
+}
+
+impl<'a> core::fmt::Display for Path<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl<'a> AsRef<str> for Path<'a> {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Path<'a> {
+    type Error = crate::Error;
+
+    /// Same as [`Path::new`].
+    fn try_from(path_str: &'a str) -> Result<Path<'a>, crate::Error> {
+        Path::new(path_str)
+    }
+}
+
+impl<'a> From<Path<'a>> for crate::FfiString<'a> {
+    /// Same as [`Path::as_ffi_string`].
+    fn from(path: Path<'a>) -> crate::FfiString<'a> {
+        path.as_ffi_string()
+    }
+}
+
+/// A set of filesystem naming rules, for use with [`Path::validate`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Profile {
+    /// Only the rules applied by [`Path::new`].
+    Generic,
+    /// FAT short (8.3) names.
+    ///
+    /// Each name is one to eight characters, optionally followed by a `.` and
+    /// one to three characters. Only the characters listed in the [`Path`]
+    /// documentation are allowed.
+    Fat83,
+    /// VFAT long names.
+    ///
+    /// Each name is up to 255 UTF-16 code units long, must not contain any of
+    /// `"*/:<>?\|`, and must not end in a `.` or a space.
+    Vfat,
+}
+
+/// Why a name in a path is not valid for a [`Profile`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InvalidReason {
+    /// The name has nothing before the `.`.
+    Empty,
+    /// The name contains a character the filesystem does not allow.
+    IllegalChar(char),
+    /// The name ends in a `.` or a space.
+    TrailingDotOrSpace,
+    /// The name has more than one `.` (only for [`Profile::Fat83`]).
+    TooManyDots,
+    /// The name (without the extension, for [`Profile::Fat83`]) is too long.
+    NameTooLong,
+    /// The extension is longer than three characters (only for
+    /// [`Profile::Fat83`]).
+    ExtensionTooLong,
+}
+
+impl core::fmt::Display for InvalidReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidReason::Empty => write!(f, "name is empty"),
+            InvalidReason::IllegalChar(ch) => write!(f, "character {:?} is not allowed", ch),
+            InvalidReason::TrailingDotOrSpace => write!(f, "name ends in '.' or ' '"),
+            InvalidReason::TooManyDots => write!(f, "name has more than one '.'"),
+            InvalidReason::NameTooLong => write!(f, "name is too long"),
+            InvalidReason::ExtensionTooLong => write!(f, "extension is too long"),
+        }
+    }
+}
+
+/// Describes a name in a path which is not valid for a [`Profile`].
+///
+/// Produced by [`Path::validate`]. Converts into
+/// [`Error::InvalidPath`](crate::Error::InvalidPath).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ValidationError<'a> {
+    /// The name which is not valid.
+    pub component: &'a str,
+    /// The position of the name in [`Path::components`], starting at zero.
+    pub index: usize,
+    /// Why the name is not valid.
+    pub reason: InvalidReason,
+}
+
+impl<'a> core::fmt::Display for ValidationError<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "invalid path component {:?} (at {}): {}",
+            self.component, self.index, self.reason
+        )
+    }
+}
+
+impl<'a> From<ValidationError<'a>> for crate::Error {
+    fn from(_value: ValidationError<'a>) -> crate::Error {
+        crate::Error::InvalidPath
+    }
+}
+
+/// The rules for comparing the case of two paths.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaseFolding {
+    /// Paths must match exactly, byte for byte.
+    Exact,
+    /// ASCII letters match regardless of case, as on FAT volumes.
+    ///
+    /// Non-ASCII characters must match exactly.
+    Ascii,
+}
+
+/// A [`Path`] which compares and hashes ignoring ASCII case.
+///
+/// Produced by [`Path::case_insensitive`].
+pub struct CaseInsensitivePath<'a>(pub Path<'a>);
+
+impl<'a> PartialEq for CaseInsensitivePath<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_with(&other.0, CaseFolding::Ascii)
+    }
+}
+
+impl<'a> Eq for CaseInsensitivePath<'a> {}
+
+impl<'a> PartialOrd for CaseInsensitivePath<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Ord for CaseInsensitivePath<'a> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.0.cmp_with(&other.0, CaseFolding::Ascii)
+    }
+}
+
+impl<'a> core::hash::Hash for CaseInsensitivePath<'a> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash_with(state, CaseFolding::Ascii)
+    }
+}
+
+/// A single piece of a [`Path`].
+///
+/// Produced by [`Path::components`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Component<'a> {
+    /// A drive specifier, like `HD0` (without the `:`).
+    Drive(&'a str),
+    /// The root directory of a drive.
+    RootDir,
+    /// A reference to the current directory, i.e. `.`.
+    CurDir,
+    /// A reference to the parent directory, i.e. `..`.
+    ParentDir,
+    /// The name of a file or directory.
+    Normal(&'a str),
+}
+
+impl<'a> Component<'a> {
+    /// View this component as a string-slice.
+    ///
+    /// Drive specifiers do not include the `:`, and the root directory is
+    /// `/`.
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            Component::Drive(drive) => drive,
+            Component::RootDir => "/",
+            Component::CurDir => ".",
+            Component::ParentDir => "..",
+            Component::Normal(name) => name,
+        }
+    }
+}
+
+/// An iterator over the [`Component`]s of a [`Path`].
+///
+/// Produced by [`Path::components`].
+#[derive(Debug, Clone)]
+pub struct Components<'a> {
+    drive: Option<&'a str>,
+    root: bool,
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = Component<'a>;
+
+    fn next(&mut self) -> Option<Component<'a>> {
+        if let Some(drive) = self.drive.take() {
+            return Some(Component::Drive(drive));
+        }
+        if self.root {
+            self.root = false;
+            return Some(Component::RootDir);
+        }
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+            let (name, rest) = self
+                .rest
+                .split_once(Path::PATH_SEP)
+                .unwrap_or((self.rest, ""));
+            self.rest = rest;
+            match name {
+                "" => continue,
+                "." => return Some(Component::CurDir),
+                ".." => return Some(Component::ParentDir),
+                name => return Some(Component::Normal(name)),
+            }
+        }
+    }
+}
+
+/// An iterator over a [`Path`] and its parents.
+///
+/// Produced by [`Path::ancestors`].
+#[derive(Debug, Clone)]
+pub struct Ancestors<'a> {
+    next: Option<Path<'a>>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = Path<'a>;
+
+    fn next(&mut self) -> Option<Path<'a>> {
+        let this = self.next.take()?;
+        self.next = this.parent();
+        Some(this)
+    }
+}
+
+/// An owned path, stored inline with a fixed capacity of `N` bytes.
+///
+/// This does not need a heap allocator. Every change to the path is checked
+/// with the same rules as [`Path::new`], and if the change would make the path
+/// invalid (or too long), the path is left unchanged.
+///
+/// ```
+/// # use neotron_api::path::PathBuf;
+/// let mut path = PathBuf::<64>::new("HD0:/DOCS/").unwrap();
+/// path.push("SALES.TXT").unwrap();
+/// assert_eq!(path.as_str(), "HD0:/DOCS/SALES.TXT");
+/// path.set_extension("BAK").unwrap();
+/// assert_eq!(path.as_str(), "HD0:/DOCS/SALES.BAK");
+/// ```
+#[derive(Clone)]
+pub struct PathBuf<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> PathBuf<N> {
+    /// Create an owned path from a string.
+    ///
+    /// If the given string is not a valid path, you get
+    /// `Err(Error::InvalidPath)`. If it is longer than `N` bytes, you get
+    /// `Err(Error::InvalidArg)`.
+    pub fn new(path_str: &str) -> Result<PathBuf<N>, crate::Error> {
+        let mut path = PathBuf::empty();
+        set(&mut path, path_str)?;
+        Ok(path)
+    }
+
+    /// Create an empty buffer.
+    ///
+    /// This is not a valid path, so it must not escape this module.
+    const fn empty() -> PathBuf<N> {
+        PathBuf {
+            buffer: [0u8; N],
+            len: 0,
+        }
+    }
+
+    /// Create an owned copy of a borrowed path.
+    ///
+    /// If it is longer than `N` bytes, you get `Err(Error::InvalidArg)`.
+    pub fn from_path(path: &Path) -> Result<PathBuf<N>, crate::Error> {
+        Self::new(path.as_str())
+    }
+
+    /// Borrow this path as a [`Path`].
+    pub fn as_path(&self) -> Path<'_> {
+        Path(PathStorage::as_str(self))
+    }
+
+    /// View this path as a string-slice.
+    pub fn as_str(&self) -> &str {
+        PathStorage::as_str(self)
+    }
+
+    /// The maximum length of path this can hold, in bytes.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Add a path to the end of this one.
+    ///
+    /// * If `path` is absolute (it has a drive specifier), it replaces this
+    ///   path entirely.
+    /// * Otherwise it is added as a new component, with a `/` in between if
+    ///   required. So `HD0:/DOCS` plus `SALES.TXT` is `HD0:/DOCS/SALES.TXT`.
+    ///
+    /// If `path` is not a valid path, you get `Err(Error::InvalidPath)`.
+    pub fn push(&mut self, path: &str) -> Result<(), crate::Error> {
+        push(self, path)
+    }
+
+    /// Remove the last component from this path.
+    ///
+    /// The result keeps its trailing `/`, as it is a directory. So
+    /// `HD0:/DOCS/SALES.TXT` becomes `HD0:/DOCS/`, which becomes `HD0:/`.
+    ///
+    /// Returns `false` (and leaves the path unchanged) if there is nothing to
+    /// remove, as with `HD0:/` or `SALES.TXT`.
+    pub fn pop(&mut self) -> bool {
+        pop(self)
+    }
+
+    /// Replace the filename portion of this path.
+    ///
+    /// If the path has no filename portion (e.g. `HD0:/DOCS/`), the filename
+    /// is added to the end. The filename must not be empty, `.` or `..`, and
+    /// must not contain `/` or `:`, or you get `Err(Error::InvalidPath)`.
+    pub fn set_file_name(&mut self, file_name: &str) -> Result<(), crate::Error> {
+        set_file_name(self, file_name)
+    }
+
+    /// Replace the extension of the filename portion of this path.
+    ///
+    /// An empty `extension` removes the extension (and the `.`). If the path
+    /// has no filename portion, or the extension contains `/`, `:` or `.`,
+    /// you get `Err(Error::InvalidPath)`.
+    ///
+    /// Leading `.`s are part of the name, not the start of an extension (see
+    /// [`Path::file_stem`]). So `.PROFILE` becomes `.PROFILE.BAK`, and
+    /// removing its extension leaves it alone.
+    pub fn set_extension(&mut self, extension: &str) -> Result<(), crate::Error> {
+        set_extension(self, extension)
+    }
+}
+
+impl<const N: usize> PathStorage for PathBuf<N> {
+    fn as_str(&self) -> &str {
+        // We only ever copy in whole `&str`s, and only ever truncate at
+        // character boundaries, so this is always valid UTF-8.
+        core::str::from_utf8(&self.buffer[..self.len]).unwrap_or("")
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    fn push_str(&mut self, s: &str) -> Result<(), crate::Error> {
+        let new_len = self.len + s.len();
+        if new_len > N {
+            return Err(crate::Error::InvalidArg);
+        }
+        self.buffer[self.len..new_len].copy_from_slice(s.as_bytes());
+        self.len = new_len;
+        Ok(())
+    }
+}
+
+impl<const N: usize> PartialEq for PathBuf<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_path() == other.as_path()
+    }
+}
+
+impl<const N: usize> Eq for PathBuf<N> {}
+
+impl<const N: usize> PartialOrd for PathBuf<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for PathBuf<N> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_path().cmp(&other.as_path())
+    }
+}
+
+impl<const N: usize> core::hash::Hash for PathBuf<N> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_path().hash(state)
+    }
+}
+
+impl<const N: usize> core::fmt::Display for PathBuf<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> AsRef<str> for PathBuf<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for PathBuf<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "PathBuf({:?})", self.as_str())
+    }
+}
+
+/// An owned path, stored on the heap.
+///
+/// Only available with the `alloc` feature. Works like [`PathBuf`], but has no
+/// fixed capacity.
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HeapPathBuf(alloc::string::String);
+
+#[cfg(feature = "alloc")]
+impl HeapPathBuf {
+    /// Create an owned path from a string.
+    ///
+    /// If the given string is not a valid path, you get
+    /// `Err(Error::InvalidPath)`.
+    pub fn new(path_str: &str) -> Result<HeapPathBuf, crate::Error> {
+        let mut path = HeapPathBuf(alloc::string::String::new());
+        set(&mut path, path_str)?;
+        Ok(path)
+    }
+
+    /// Create an owned copy of a borrowed path.
+    pub fn from_path(path: &Path) -> HeapPathBuf {
+        HeapPathBuf(alloc::string::String::from(path.as_str()))
+    }
+
+    /// Borrow this path as a [`Path`].
+    pub fn as_path(&self) -> Path<'_> {
+        Path(&self.0)
+    }
+
+    /// View this path as a string-slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Add a path to the end of this one.
+    ///
+    /// See [`PathBuf::push`] - they work the same way.
+    pub fn push(&mut self, path: &str) -> Result<(), crate::Error> {
+        push(self, path)
+    }
+
+    /// Remove the last component from this path.
+    ///
+    /// See [`PathBuf::pop`] - they work the same way.
+    pub fn pop(&mut self) -> bool {
+        pop(self)
+    }
+
+    /// Replace the filename portion of this path.
+    ///
+    /// See [`PathBuf::set_file_name`] - they work the same way.
+    pub fn set_file_name(&mut self, file_name: &str) -> Result<(), crate::Error> {
+        set_file_name(self, file_name)
+    }
+
+    /// Replace the extension of the filename portion of this path.
+    ///
+    /// See [`PathBuf::set_extension`] - they work the same way.
+    pub fn set_extension(&mut self, extension: &str) -> Result<(), crate::Error> {
+        set_extension(self, extension)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PathStorage for HeapPathBuf {
+    fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    fn push_str(&mut self, s: &str) -> Result<(), crate::Error> {
+        self.0.push_str(s);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for HeapPathBuf {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<str> for HeapPathBuf {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Debug for HeapPathBuf {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "HeapPathBuf({:?})", self.0)
+    }
+}
+
+/// The operations the owned path types need from their storage.
+trait PathStorage: Clone {
+    /// View the contents as a string-slice.
+    fn as_str(&self) -> &str;
+
+    /// Shorten the contents to `len` bytes.
+    fn truncate(&mut self, len: usize);
+
+    /// Add to the end of the contents.
+    fn push_str(&mut self, s: &str) -> Result<(), crate::Error>;
+}
+
+/// An 8.3 short filename, as used on FAT volumes.
+///
+/// Stored in the same zero-padded form as [`crate::dir::Entry::name`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShortName([u8; crate::MAX_FILENAME_LEN]);
+
+impl ShortName {
+    /// The largest numeric tail we will try, like the `1` in `MYDOCU~1`.
+    const MAX_TAIL: u32 = 999_999;
+
+    /// Create a short name from a string which is already a valid 8.3 name.
+    ///
+    /// See [`crate::dir::Entry::new`] for the rules. Lower-case letters are
+    /// converted to upper-case. If the name is not valid, you get
+    /// `Err(Error::InvalidPath)`.
+    pub fn new(name: &str) -> Result<ShortName, crate::Error> {
+        crate::dir::encode_name(name).map(ShortName)
+    }
+
+    /// Generate a short name for the given long filename.
+    ///
+    /// The long name is uppercased, spaces and leading dots are removed, and
+    /// any characters not allowed in a short name (see [`Path`]) are replaced
+    /// with `_`. The basename is cut to eight characters and the extension
+    /// (everything after the last `.`) is cut to three characters.
+    ///
+    /// If nothing had to be removed or replaced, and the result is not already
+    /// taken, the result is used as-is. So `readme.txt` becomes `README.TXT`.
+    /// Otherwise a numeric tail is added, so `My Documents` becomes
+    /// `MYDOCU~1` - or `MYDOCU~2` if `MYDOCU~1` is taken, and so on.
+    ///
+    /// The `exists` function is called with each candidate short name, and
+    /// should return `true` if there is already a file or directory with that
+    /// name. Candidates are always in upper-case.
+    ///
+    /// If the long name contains no usable characters (or contains `/`, `:`
+    /// or control characters), you get `Err(Error::InvalidPath)`. If every
+    /// candidate is taken, you get `Err(Error::InvalidArg)`.
+    pub fn from_long_name<F>(long_name: &str, mut exists: F) -> Result<ShortName, crate::Error>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        if long_name
+            .chars()
+            .any(|ch| ch.is_control() || ch == Path::PATH_SEP || ch == Path::DRIVE_SEP)
+        {
+            return Err(crate::Error::InvalidPath);
+        }
+        let trimmed = long_name.trim_start_matches(['.', ' ']);
+        let mut lossy = trimmed.len() != long_name.len();
+        let (long_basename, long_extension) = match trimmed.rsplit_once('.') {
+            Some((basename, extension)) => (basename, extension),
+            None => (trimmed, ""),
+        };
+
+        let mut basename = [0u8; 8];
+        let (basename_len, basename_lossy) = Self::convert(long_basename, &mut basename);
+        let mut extension = [0u8; 3];
+        let (extension_len, extension_lossy) = Self::convert(long_extension, &mut extension);
+        lossy |= basename_lossy || extension_lossy;
+        if basename_len == 0 {
+            return Err(crate::Error::InvalidPath);
+        }
+
+        if !lossy {
+            let candidate =
+                Self::build(&basename[..basename_len], &[], &extension[..extension_len]);
+            if !exists(candidate.as_str()) {
+                return Ok(candidate);
+            }
+        }
+
+        for tail_number in 1..=Self::MAX_TAIL {
+            let mut tail = [0u8; 7];
+            let tail_len = Self::write_tail(tail_number, &mut tail);
+            let keep = basename_len.min(8 - tail_len);
+            let candidate = Self::build(
+                &basename[..keep],
+                &tail[..tail_len],
+                &extension[..extension_len],
+            );
+            if !exists(candidate.as_str()) {
+                return Ok(candidate);
+            }
+        }
+        Err(crate::Error::InvalidArg)
+    }
+
+    /// Get the short name as a string, e.g. `README.TXT`.
+    pub fn as_str(&self) -> &str {
+        crate::dir::decode_name(&self.0)
+    }
+
+    /// Get the short name in the form used by [`crate::dir::Entry::name`].
+    pub fn as_bytes(&self) -> &[u8; crate::MAX_FILENAME_LEN] {
+        &self.0
+    }
+
+    /// Convert part of a long name into short name characters.
+    ///
+    /// Returns how many bytes were written, and whether anything was lost in
+    /// the conversion.
+    fn convert(long: &str, output: &mut [u8]) -> (usize, bool) {
+        let mut len = 0;
+        let mut lossy = false;
+        for ch in long.chars() {
+            let byte = match u8::try_from(ch) {
+                Ok(b' ') | Ok(b'.') => {
+                    lossy = true;
+                    continue;
+                }
+                Ok(byte) if is_short_name_char(byte) => byte.to_ascii_uppercase(),
+                _ => {
+                    lossy = true;
+                    b'_'
+                }
+            };
+            if len == output.len() {
+                lossy = true;
+                break;
+            }
+            output[len] = byte;
+            len += 1;
+        }
+        (len, lossy)
+    }
+
+    /// Write a tail like `~12` into the buffer, returning the length.
+    fn write_tail(mut number: u32, output: &mut [u8; 7]) -> usize {
+        let mut digits = [0u8; 6];
+        let mut num_digits = 0;
+        while number > 0 {
+            digits[num_digits] = b'0' + (number % 10) as u8;
+            number /= 10;
+            num_digits += 1;
+        }
+        output[0] = b'~';
+        for (out, digit) in output[1..]
+            .iter_mut()
+            .zip(digits[..num_digits].iter().rev())
+        {
+            *out = *digit;
+        }
+        num_digits + 1
+    }
+
+    /// Assemble a short name from its parts.
+    ///
+    /// The parts must already be valid and short enough.
+    fn build(basename: &[u8], tail: &[u8], extension: &[u8]) -> ShortName {
+        let mut name = [0u8; crate::MAX_FILENAME_LEN];
+        let mut len = 0;
+        for &byte in basename.iter().chain(tail) {
+            name[len] = byte;
+            len += 1;
+        }
+        if !extension.is_empty() {
+            name[len] = b'.';
+            len += 1;
+            name[len..len + extension.len()].copy_from_slice(extension);
+        }
+        ShortName(name)
+    }
+}
+
+impl core::fmt::Display for ShortName {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 // ============================================================================
 // Functions
 // ============================================================================
 
-// None
+/// Make a change to an owned path, undoing it if it fails or leaves the path
+/// invalid.
+fn modify<S, F>(storage: &mut S, change: F) -> Result<(), crate::Error>
+where
+    S: PathStorage,
+    F: FnOnce(&mut S) -> Result<(), crate::Error>,
+{
+    let backup = storage.clone();
+    let result = change(storage).and_then(|_| Path::new(storage.as_str()).map(|_| ()));
+    if result.is_err() {
+        *storage = backup;
+    }
+    result
+}
+
+/// Replace the contents of an owned path.
+fn set<S: PathStorage>(storage: &mut S, path_str: &str) -> Result<(), crate::Error> {
+    Path::new(path_str)?;
+    modify(storage, |storage| {
+        storage.truncate(0);
+        storage.push_str(path_str)
+    })
+}
+
+/// See [`PathBuf::push`].
+fn push<S: PathStorage>(storage: &mut S, path_str: &str) -> Result<(), crate::Error> {
+    let path = Path::new(path_str)?;
+    if path.is_absolute_path() {
+        return set(storage, path_str);
+    }
+    modify(storage, |storage| {
+        if !storage.as_str().ends_with(Path::PATH_SEP) {
+            storage.push_str("/")?;
+        }
+        storage.push_str(path_str)
+    })
+}
+
+/// See [`PathBuf::pop`].
+fn pop<S: PathStorage>(storage: &mut S) -> bool {
+    match Path(storage.as_str()).parent() {
+        Some(parent) => {
+            let len = parent.as_str().len();
+            storage.truncate(len);
+            true
+        }
+        None => false,
+    }
+}
+
+/// See [`PathBuf::set_file_name`].
+fn set_file_name<S: PathStorage>(storage: &mut S, file_name: &str) -> Result<(), crate::Error> {
+    if matches!(file_name, "" | "." | "..") || file_name.contains([Path::PATH_SEP, Path::DRIVE_SEP])
+    {
+        return Err(crate::Error::InvalidPath);
+    }
+    let path = Path(storage.as_str());
+    let keep = match path.filename() {
+        Some(old_name) => path.as_str().len() - old_name.len(),
+        None => path.as_str().len(),
+    };
+    modify(storage, |storage| {
+        storage.truncate(keep);
+        if storage.as_str().ends_with(Path::DRIVE_SEP) {
+            storage.push_str("/")?;
+        }
+        storage.push_str(file_name)
+    })
+}
+
+/// See [`PathBuf::set_extension`].
+fn set_extension<S: PathStorage>(storage: &mut S, extension: &str) -> Result<(), crate::Error> {
+    if extension.contains([Path::PATH_SEP, Path::DRIVE_SEP, '.']) {
+        return Err(crate::Error::InvalidPath);
+    }
+    let path = Path(storage.as_str());
+    let Some(file_name) = path.filename() else {
+        return Err(crate::Error::InvalidPath);
+    };
+    let (stem, _old_extension) = split_extension(file_name);
+    let keep = path.as_str().len() - file_name.len() + stem.len();
+    modify(storage, |storage| {
+        storage.truncate(keep);
+        if !extension.is_empty() {
+            storage.push_str(".")?;
+            storage.push_str(extension)?;
+        }
+        Ok(())
+    })
+}
+
+/// See [`Path::normalise`].
+///
+/// The `output` must start off empty.
+fn normalise_into<S: PathStorage>(path: &Path, output: &mut S) -> Result<(), crate::Error> {
+    normalise_components(path.components(), path.0.ends_with(Path::PATH_SEP), output)
+}
+
+/// See [`Path::resolve`].
+///
+/// The `output` must start off empty.
+fn resolve_into<S: PathStorage>(
+    path: &Path,
+    cwd: &Path,
+    output: &mut S,
+) -> Result<(), crate::Error> {
+    if path.is_absolute_path() {
+        normalise_into(path, output)
+    } else if cwd.is_absolute_path() {
+        let components = cwd.components().chain(path.components());
+        normalise_components(components, path.0.ends_with(Path::PATH_SEP), output)
+    } else {
+        Err(crate::Error::InvalidPath)
+    }
+}
+
+/// Normalise a sequence of path components into the `output`.
+///
+/// The `output` must start off empty. If `is_dir` is set, the result will end
+/// with a `/`.
+fn normalise_components<'a, I, S>(
+    components: I,
+    is_dir: bool,
+    output: &mut S,
+) -> Result<(), crate::Error>
+where
+    I: Iterator<Item = Component<'a>>,
+    S: PathStorage,
+{
+    // Where the first directory name starts in the output
+    let mut base_len = 0;
+    // How many names are in the output that a `..` could remove
+    let mut depth = 0;
+    let mut is_absolute = false;
+    let mut last = None;
+    for component in components {
+        match component {
+            Component::Drive(drive) => {
+                output.push_str(drive)?;
+                output.push_str(":")?;
+                is_absolute = true;
+            }
+            Component::RootDir => {
+                output.push_str("/")?;
+                base_len = output.as_str().len();
+            }
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                // Remove the last name, which ends with a `/`
+                let current = output.as_str();
+                let without_sep = &current[..current.len() - 1];
+                let keep = without_sep
+                    .rfind(Path::PATH_SEP)
+                    .map_or(0, |idx| idx + 1)
+                    .max(base_len);
+                output.truncate(keep);
+                depth -= 1;
+            }
+            Component::ParentDir if is_absolute => {
+                return Err(crate::Error::InvalidPath);
+            }
+            Component::ParentDir => {
+                output.push_str("../")?;
+                base_len = output.as_str().len();
+            }
+            Component::Normal(name) => {
+                output.push_str(name)?;
+                output.push_str("/")?;
+                depth += 1;
+            }
+        }
+        last = Some(component);
+    }
+    let is_file = matches!(last, Some(Component::Normal(_))) && !is_dir;
+    if is_file {
+        // Remove the trailing `/` we added
+        let len = output.as_str().len();
+        output.truncate(len - 1);
+    } else if output.as_str().is_empty() {
+        output.push_str("./")?;
+    }
+    Ok(())
+}
+
+/// Split a path into the length of the drive specifier (including the `:`)
+/// and the rest.
+///
+/// Returns `None` if the path is empty or is a bare drive.
+fn split_drive(path: &str) -> Option<(usize, &str)> {
+    let drive_specifier_len = path.find(Path::DRIVE_SEP).map_or(0, |idx| idx + 1);
+    let rest = &path[drive_specifier_len..];
+    if rest.is_empty() {
+        None
+    } else {
+        Some((drive_specifier_len, rest))
+    }
+}
+
+/// Does a filename match a DOS-style wildcard pattern?
+///
+/// * `*` matches any number of characters (including none).
+/// * `?` matches exactly one character.
+/// * Everything else must match exactly, except that ASCII letters are
+///   compared case-insensitively (as on FAT volumes).
+///
+/// As on MS-DOS, a pattern ending in `.*` also matches names with no
+/// extension, so `*.*` matches everything and `README.*` matches `README`.
+/// A pattern ending in `.` asks for an empty extension, so `*.` matches
+/// `README` but not `README.TXT`.
+///
+/// The pattern and name should be single filenames, not paths with `/` in.
+///
+/// ```
+/// # use neotron_api::path::wildcard_match;
+/// assert!(wildcard_match("*.TXT", "sales.txt"));
+/// assert!(wildcard_match("REPORT??.DOC", "REPORT01.DOC"));
+/// assert!(!wildcard_match("REPORT??.DOC", "REPORT1.DOC"));
+/// ```
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    if wildcard_match_inner(pattern, name) {
+        return true;
+    }
+    if name.contains('.') {
+        return false;
+    }
+    match pattern
+        .strip_suffix(".*")
+        .or_else(|| pattern.strip_suffix('.'))
+    {
+        Some(prefix) => wildcard_match_inner(prefix, name),
+        None => false,
+    }
+}
+
+/// Does this string contain any wildcard characters?
+///
+/// See [`wildcard_match`].
+pub fn is_wildcard(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Match a name against a pattern, without the special `.*` handling.
+///
+/// This is the usual greedy algorithm - when we hit a mismatch we go back to
+/// the most recent `*` and let it swallow one more character.
+fn wildcard_match_inner(pattern: &str, name: &str) -> bool {
+    let mut pattern_chars = pattern.chars();
+    let mut name_chars = name.chars();
+    // Where to resume from if we need to go back to the last `*`
+    let mut backtrack: Option<(core::str::Chars, core::str::Chars)> = None;
+    loop {
+        let name_before = name_chars.clone();
+        match (pattern_chars.next(), name_chars.next()) {
+            (Some('*'), _) => {
+                // Initially, let the `*` match nothing
+                backtrack = Some((pattern_chars.clone(), name_before.clone()));
+                name_chars = name_before;
+                continue;
+            }
+            (Some('?'), Some(_)) => continue,
+            (Some(p), Some(n)) if p.eq_ignore_ascii_case(&n) => continue,
+            (None, None) => return true,
+            _ => {}
+        }
+        // Mismatch - let the last `*` swallow one more character
+        let Some((star_pattern, star_name)) = backtrack.as_mut() else {
+            return false;
+        };
+        if star_name.next().is_none() {
+            return false;
+        }
+        pattern_chars = star_pattern.clone();
+        name_chars = star_name.clone();
+    }
+}
+
+/// Check a name against the rules in [`Profile::Fat83`].
+fn validate_fat83(name: &str) -> Result<(), InvalidReason> {
+    if name.ends_with(['.', ' ']) {
+        return Err(InvalidReason::TrailingDotOrSpace);
+    }
+    let (basename, extension) = name.split_once('.').unwrap_or((name, ""));
+    if basename.is_empty() {
+        return Err(InvalidReason::Empty);
+    }
+    if extension.contains('.') {
+        return Err(InvalidReason::TooManyDots);
+    }
+    let illegal = name
+        .chars()
+        .find(|&ch| ch != '.' && !u8::try_from(ch).is_ok_and(is_short_name_char));
+    if let Some(ch) = illegal {
+        return Err(InvalidReason::IllegalChar(ch));
+    }
+    if basename.len() > 8 {
+        return Err(InvalidReason::NameTooLong);
+    }
+    if extension.len() > 3 {
+        return Err(InvalidReason::ExtensionTooLong);
+    }
+    Ok(())
+}
+
+/// Check a name against the rules in [`Profile::Vfat`].
+fn validate_vfat(name: &str) -> Result<(), InvalidReason> {
+    if let Some(ch) = name.chars().find(|ch| "\"*/:<>?\\|".contains(*ch)) {
+        return Err(InvalidReason::IllegalChar(ch));
+    }
+    if name.ends_with(['.', ' ']) {
+        return Err(InvalidReason::TrailingDotOrSpace);
+    }
+    if name.encode_utf16().count() > 255 {
+        return Err(InvalidReason::NameTooLong);
+    }
+    Ok(())
+}
+
+/// Split a filename into its stem and its extension (if any).
+///
+/// The extension is everything after the last `.`, except that leading `.`s
+/// are part of the stem - so `.PROFILE` is all stem, with no extension.
+fn split_extension(filename: &str) -> (&str, Option<&str>) {
+    let leading_dots = filename.len() - filename.trim_start_matches('.').len();
+    match filename[leading_dots..].rfind('.') {
+        Some(idx) => {
+            let (stem, dot_extension) = filename.split_at(leading_dots + idx);
+            (stem, Some(&dot_extension[1..]))
+        }
+        None => (filename, None),
+    }
+}
+
+/// Is this byte allowed in a FAT short (8.3) filename?
+///
+/// We only allow the ASCII characters listed in the [`Path`] documentation.
+pub(crate) fn is_short_name_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"$%-_@~`!(){}^#&".contains(&byte)
+}
 
 // ============================================================================
 // Tests
@@ -211,6 +1463,8 @@ impl<'a> Path<'a> {
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
+
     use super::*;
 
     #[test]
@@ -223,6 +1477,8 @@ mod tests {
         assert_eq!(path.directory(), Some("/DOCUMENTS/JUNE"));
         assert_eq!(path.filename(), Some("SALES.TXT"));
         assert_eq!(path.extension(), Some("TXT"));
+        assert_eq!(path.file_stem(), Some("SALES"));
+        assert_eq!(path.parent().unwrap().as_str(), "HD0:/DOCUMENTS/JUNE/");
     }
 
     #[test]
@@ -235,6 +1491,8 @@ mod tests {
         assert_eq!(path.directory(), Some("/"));
         assert_eq!(path.filename(), None);
         assert_eq!(path.extension(), None);
+        assert_eq!(path.file_stem(), None);
+        assert!(path.parent().is_none());
     }
 
     #[test]
@@ -247,6 +1505,8 @@ mod tests {
         assert_eq!(path.directory(), Some("DOCUMENTS/JUNE"));
         assert_eq!(path.filename(), Some("SALES.TXT"));
         assert_eq!(path.extension(), Some("TXT"));
+        assert_eq!(path.file_stem(), Some("SALES"));
+        assert_eq!(path.parent().unwrap().as_str(), "DOCUMENTS/JUNE/");
     }
 
     #[test]
@@ -259,6 +1519,8 @@ mod tests {
         assert_eq!(path.directory(), Some("/DOCUMENTS/JUNE"));
         assert_eq!(path.filename(), None);
         assert_eq!(path.extension(), None);
+        assert_eq!(path.file_stem(), None);
+        assert_eq!(path.parent().unwrap().as_str(), "HD0:/DOCUMENTS/");
     }
 
     #[test]
@@ -271,6 +1533,619 @@ mod tests {
         assert_eq!(path.directory(), Some("DOCUMENTS"));
         assert_eq!(path.filename(), None);
         assert_eq!(path.extension(), None);
+        assert_eq!(path.file_stem(), None);
+        assert!(path.parent().is_none());
+    }
+
+    #[test]
+    fn components() {
+        let path = Path::new("HD0:/DOCUMENTS//JUNE/./../SALES.TXT").unwrap();
+        let mut components = path.components();
+        assert_eq!(components.next(), Some(Component::Drive("HD0")));
+        assert_eq!(components.next(), Some(Component::RootDir));
+        assert_eq!(components.next(), Some(Component::Normal("DOCUMENTS")));
+        assert_eq!(components.next(), Some(Component::Normal("JUNE")));
+        assert_eq!(components.next(), Some(Component::CurDir));
+        assert_eq!(components.next(), Some(Component::ParentDir));
+        assert_eq!(components.next(), Some(Component::Normal("SALES.TXT")));
+        assert_eq!(components.next(), None);
+
+        let path = Path::new("HD0:").unwrap();
+        let mut components = path.components();
+        assert_eq!(components.next(), Some(Component::Drive("HD0")));
+        assert_eq!(components.next(), Some(Component::RootDir));
+        assert_eq!(components.next(), None);
+
+        let path = Path::new("DOCUMENTS/JUNE/").unwrap();
+        let mut components = path.components();
+        assert_eq!(components.next().map(|c| c.as_str()), Some("DOCUMENTS"));
+        assert_eq!(components.next().map(|c| c.as_str()), Some("JUNE"));
+        assert_eq!(components.next(), None);
+    }
+
+    #[test]
+    fn ancestors() {
+        let path = Path::new("HD0:/DOCUMENTS/JUNE/SALES.TXT").unwrap();
+        let mut ancestors = path.ancestors();
+        assert_eq!(
+            ancestors.next().map(|p| p.0),
+            Some("HD0:/DOCUMENTS/JUNE/SALES.TXT")
+        );
+        assert_eq!(ancestors.next().map(|p| p.0), Some("HD0:/DOCUMENTS/JUNE/"));
+        assert_eq!(ancestors.next().map(|p| p.0), Some("HD0:/DOCUMENTS/"));
+        assert_eq!(ancestors.next().map(|p| p.0), Some("HD0:/"));
+        assert!(ancestors.next().is_none());
+
+        let path = Path::new("DOCUMENTS/JUNE").unwrap();
+        let mut ancestors = path.ancestors();
+        assert_eq!(ancestors.next().map(|p| p.0), Some("DOCUMENTS/JUNE"));
+        assert_eq!(ancestors.next().map(|p| p.0), Some("DOCUMENTS/"));
+        assert!(ancestors.next().is_none());
+    }
+
+    fn normalise(path_str: &str) -> Result<PathBuf<64>, crate::Error> {
+        Path::new(path_str).unwrap().normalise()
+    }
+
+    #[test]
+    fn normalise_absolute() {
+        let cases = [
+            ("HD0:/DOCS/../SALES.TXT", "HD0:/SALES.TXT"),
+            ("HD0:/A/./B", "HD0:/A/B"),
+            ("HD0:/A//B///C/", "HD0:/A/B/C/"),
+            ("HD0:/A/B/..", "HD0:/A/"),
+            ("HD0:/A/B/.", "HD0:/A/B/"),
+            ("HD0:/A/..", "HD0:/"),
+            ("HD0:", "HD0:/"),
+            ("HD0:/", "HD0:/"),
+            ("HD0:/./SALES.TXT", "HD0:/SALES.TXT"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalise(input).unwrap().as_str(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn normalise_relative() {
+        let cases = [
+            ("DOCS/../SALES.TXT", "SALES.TXT"),
+            ("./DOCS/", "DOCS/"),
+            ("DOCS/..", "./"),
+            (".", "./"),
+            ("../../A/./B", "../../A/B"),
+            ("A/../../B", "../B"),
+            ("../A/..", "../"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalise(input).unwrap().as_str(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn normalise_above_root() {
+        assert_eq!(normalise("HD0:/..").unwrap_err(), crate::Error::InvalidPath);
+        assert_eq!(
+            normalise("HD0:/A/../../B").unwrap_err(),
+            crate::Error::InvalidPath
+        );
+        let path = Path::new("HD0:/A/B/C/D/E").unwrap();
+        assert_eq!(path.normalise::<8>().unwrap_err(), crate::Error::InvalidArg);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn normalise_alloc() {
+        let path = Path::new("HD0:/A/./B/../C.TXT").unwrap();
+        assert_eq!(path.normalise_alloc().unwrap().as_str(), "HD0:/A/C.TXT");
+    }
+
+    #[test]
+    fn resolve() {
+        let cwd = Path::new("HD0:/DOCS/JUNE").unwrap();
+        let cases = [
+            ("SALES.TXT", "HD0:/DOCS/JUNE/SALES.TXT"),
+            ("../SALES.TXT", "HD0:/DOCS/SALES.TXT"),
+            ("./", "HD0:/DOCS/JUNE/"),
+            ("../../", "HD0:/"),
+            ("2023/", "HD0:/DOCS/JUNE/2023/"),
+            ("SD0:/A/../B", "SD0:/B"),
+        ];
+        for (input, expected) in cases {
+            let path = Path::new(input).unwrap();
+            let resolved: PathBuf<64> = path.resolve(&cwd).unwrap();
+            assert_eq!(resolved.as_str(), expected, "{input}");
+        }
+        let cwd = Path::new("HD0:").unwrap();
+        let path = Path::new("SALES.TXT").unwrap();
+        assert_eq!(path.resolve::<64>(&cwd).unwrap().as_str(), "HD0:/SALES.TXT");
+        let path = Path::new("../SALES.TXT").unwrap();
+        assert_eq!(
+            path.resolve::<64>(&cwd).unwrap_err(),
+            crate::Error::InvalidPath
+        );
+        let cwd = Path::new("DOCS").unwrap();
+        assert_eq!(
+            path.resolve::<64>(&cwd).unwrap_err(),
+            crate::Error::InvalidPath
+        );
+    }
+
+    #[test]
+    fn wildcards() {
+        let cases = [
+            ("*.TXT", "SALES.TXT", true),
+            ("*.TXT", "sales.txt", true),
+            ("*.txt", "SALES.TXT", true),
+            ("*.TXT", "SALES.DOC", false),
+            ("*.TXT", "SALES.TXT.BAK", false),
+            ("REPORT??.DOC", "REPORT01.DOC", true),
+            ("REPORT??.DOC", "REPORT1.DOC", false),
+            ("REPORT??.DOC", "REPORT123.DOC", false),
+            ("*", "ANYTHING", true),
+            ("*", "", true),
+            ("*.*", "README", true),
+            ("*.*", "README.TXT", true),
+            ("README.*", "README", true),
+            ("README.*", "READ", false),
+            ("A*B*C", "AXXBYYC", true),
+            ("A*B*C", "AXXBYY", false),
+            ("*AB", "AAB", true),
+            ("?", "", false),
+            ("", "", true),
+            ("", "A", false),
+            ("*\u{20ac}*", "Sales in \u{20ac}.xls", true),
+            ("S?LES", "S\u{20ac}LES", true),
+        ];
+        for (pattern, name, expected) in cases {
+            assert_eq!(
+                wildcard_match(pattern, name),
+                expected,
+                "{pattern:?} vs {name:?}"
+            );
+        }
+        assert!(is_wildcard("*.TXT"));
+        assert!(is_wildcard("A?"));
+        assert!(!is_wildcard("A.TXT"));
+    }
+
+    #[test]
+    fn validate_generic() {
+        let path = Path::new("HD0:/My Documents/a*b?.html").unwrap();
+        assert_eq!(path.validate(Profile::Generic), Ok(()));
+    }
+
+    #[test]
+    fn validate_fat83() {
+        let path = Path::new("HD0:/MYDOCU~1/SALES.TXT").unwrap();
+        assert_eq!(path.validate(Profile::Fat83), Ok(()));
+        let path = Path::new("../docs/./readme").unwrap();
+        assert_eq!(path.validate(Profile::Fat83), Ok(()));
+        let cases = [
+            (
+                "HD0:/DOCS/SALES.TEXT",
+                "SALES.TEXT",
+                3,
+                InvalidReason::ExtensionTooLong,
+            ),
+            (
+                "HD0:/DOCUMENTS/",
+                "DOCUMENTS",
+                2,
+                InvalidReason::NameTooLong,
+            ),
+            ("A/B.C.D", "B.C.D", 1, InvalidReason::TooManyDots),
+            ("A/.PROFILE", ".PROFILE", 1, InvalidReason::Empty),
+            ("README.", "README.", 0, InvalidReason::TrailingDotOrSpace),
+            ("A+B.TXT", "A+B.TXT", 0, InvalidReason::IllegalChar('+')),
+            ("MY DOCS/", "MY DOCS", 0, InvalidReason::IllegalChar(' ')),
+            (
+                "CAF\u{c9}",
+                "CAF\u{c9}",
+                0,
+                InvalidReason::IllegalChar('\u{c9}'),
+            ),
+        ];
+        for (path_str, component, index, reason) in cases {
+            let path = Path::new(path_str).unwrap();
+            let expected = ValidationError {
+                component,
+                index,
+                reason,
+            };
+            assert_eq!(path.validate(Profile::Fat83), Err(expected), "{path_str}");
+        }
+    }
+
+    #[test]
+    fn validate_vfat() {
+        let path = Path::new("HD0:/My Documents/Sales in \u{20ac}.xls.bak").unwrap();
+        assert_eq!(path.validate(Profile::Vfat), Ok(()));
+        let path = Path::new("HD0:/DOCS/A*B.TXT").unwrap();
+        let error = path.validate(Profile::Vfat).unwrap_err();
+        assert_eq!(error.component, "A*B.TXT");
+        assert_eq!(error.index, 3);
+        assert_eq!(error.reason, InvalidReason::IllegalChar('*'));
+        assert_eq!(crate::Error::from(error), crate::Error::InvalidPath);
+        let path = Path::new("DOCS /").unwrap();
+        assert_eq!(
+            path.validate(Profile::Vfat).unwrap_err().reason,
+            InvalidReason::TrailingDotOrSpace
+        );
+        let long_name = "X".repeat(256);
+        let path = Path::new(&long_name).unwrap();
+        assert_eq!(
+            path.validate(Profile::Vfat).unwrap_err().reason,
+            InvalidReason::NameTooLong
+        );
+        use std::string::ToString;
+        assert_eq!(
+            Path::new("A>B")
+                .unwrap()
+                .validate(Profile::Vfat)
+                .unwrap_err()
+                .to_string(),
+            "invalid path component \"A>B\" (at 0): character '>' is not allowed"
+        );
+    }
+
+    #[test]
+    fn standard_traits() {
+        use std::{format, string::ToString};
+        let path = Path::try_from("HD0:/DOCS/SALES.TXT").unwrap();
+        let copy = path;
+        assert_eq!(path, copy);
+        assert_eq!(path.to_string(), "HD0:/DOCS/SALES.TXT");
+        assert_eq!(format!("{:?}", path), "Path(\"HD0:/DOCS/SALES.TXT\")");
+        assert_eq!(path.as_ref(), "HD0:/DOCS/SALES.TXT");
+        assert_eq!(
+            Path::try_from("/DOCS").unwrap_err(),
+            crate::Error::InvalidPath
+        );
+        let ffi_string = crate::FfiString::from(path);
+        assert_eq!(ffi_string.as_str(), "HD0:/DOCS/SALES.TXT");
+        // No copy was made
+        assert_eq!(
+            path.as_ffi_string().as_str().as_ptr(),
+            path.as_str().as_ptr()
+        );
+        let path_buf = PathBuf::<32>::new("HD0:/").unwrap();
+        assert_eq!(path_buf.to_string(), "HD0:/");
+        assert_eq!(format!("{:?}", path_buf), "PathBuf(\"HD0:/\")");
+    }
+
+    fn hash_of(path: &Path, folding: CaseFolding) -> u64 {
+        use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hasher};
+        let mut hasher = BuildHasherDefault::<DefaultHasher>::default().build_hasher();
+        path.hash_with(&mut hasher, folding);
+        hasher.finish()
+    }
+
+    #[test]
+    fn compare_exact() {
+        let upper = Path::new("HD0:/FOO.TXT").unwrap();
+        let lower = Path::new("hd0:/foo.txt").unwrap();
+        assert!(upper != lower);
+        assert!(upper == Path::new("HD0:/FOO.TXT").unwrap());
+        assert!(upper < lower);
+        assert!(!upper.eq_with(&lower, CaseFolding::Exact));
+        assert_ne!(
+            hash_of(&upper, CaseFolding::Exact),
+            hash_of(&lower, CaseFolding::Exact)
+        );
+    }
+
+    #[test]
+    fn compare_case_insensitive() {
+        let upper = Path::new("HD0:/FOO.TXT").unwrap();
+        let lower = Path::new("hd0:/foo.txt").unwrap();
+        let other = Path::new("HD0:/BAR.TXT").unwrap();
+        assert!(upper.eq_with(&lower, CaseFolding::Ascii));
+        assert!(!upper.eq_with(&other, CaseFolding::Ascii));
+        assert_eq!(
+            hash_of(&upper, CaseFolding::Ascii),
+            hash_of(&lower, CaseFolding::Ascii)
+        );
+        assert_eq!(
+            other.cmp_with(&lower, CaseFolding::Ascii),
+            core::cmp::Ordering::Less
+        );
+        // Only ASCII is folded
+        let e_acute = Path::new("CAF\u{c9}").unwrap();
+        let e_acute_lower = Path::new("caf\u{e9}").unwrap();
+        assert!(!e_acute.eq_with(&e_acute_lower, CaseFolding::Ascii));
+
+        let mut set = std::collections::BTreeSet::new();
+        set.insert(upper.case_insensitive());
+        assert!(set.contains(&lower.case_insensitive()));
+        assert!(!set.contains(&other.case_insensitive()));
+        let mut set = std::collections::HashSet::new();
+        set.insert(Path::new("A/B").unwrap().case_insensitive());
+        assert!(set.contains(&Path::new("a/b").unwrap().case_insensitive()));
+    }
+
+    #[test]
+    fn compare_path_buf() {
+        let a = PathBuf::<16>::new("HD0:/A").unwrap();
+        let b = PathBuf::<16>::new("HD0:/B").unwrap();
+        assert!(a < b);
+        assert_eq!(a, PathBuf::<16>::new("HD0:/A").unwrap());
+        assert!(a
+            .as_path()
+            .case_insensitive()
+            .eq(&Path::new("hd0:/a").unwrap().case_insensitive()));
+    }
+
+    fn short_name(long_name: &str, existing: &[&str]) -> Result<ShortName, crate::Error> {
+        ShortName::from_long_name(long_name, |candidate| existing.contains(&candidate))
+    }
+
+    #[test]
+    fn short_name_unchanged() {
+        assert_eq!(
+            short_name("README.TXT", &[]).unwrap().as_str(),
+            "README.TXT"
+        );
+        assert_eq!(
+            short_name("readme.txt", &[]).unwrap().as_str(),
+            "README.TXT"
+        );
+        assert_eq!(short_name("BootLdr", &[]).unwrap().as_str(), "BOOTLDR");
+        assert_eq!(
+            short_name("README.TXT", &[]).unwrap().as_bytes(),
+            crate::dir::encode_name("README.TXT").as_ref().unwrap()
+        );
+    }
+
+    #[test]
+    fn short_name_with_tail() {
+        assert_eq!(
+            short_name("My Documents", &[]).unwrap().as_str(),
+            "MYDOCU~1"
+        );
+        assert_eq!(
+            short_name("Sales in \u{20ac}.xls", &[]).unwrap().as_str(),
+            "SALESI~1.XLS"
+        );
+        assert_eq!(
+            short_name("archive.tar.gz", &[]).unwrap().as_str(),
+            "ARCHIV~1.GZ"
+        );
+        assert_eq!(short_name(".profile", &[]).unwrap().as_str(), "PROFIL~1");
+        assert_eq!(
+            short_name("a+b=c.html", &[]).unwrap().as_str(),
+            "A_B_C~1.HTM"
+        );
+        assert_eq!(short_name("AB.C", &[]).unwrap().as_str(), "AB.C");
+    }
+
+    #[test]
+    fn short_name_collisions() {
+        assert_eq!(
+            short_name("My Documents", &["MYDOCU~1", "MYDOCU~2"])
+                .unwrap()
+                .as_str(),
+            "MYDOCU~3"
+        );
+        assert_eq!(
+            short_name("README.TXT", &["README.TXT"]).unwrap().as_str(),
+            "README~1.TXT"
+        );
+        let taken = [
+            "MYDOCU~1", "MYDOCU~2", "MYDOCU~3", "MYDOCU~4", "MYDOCU~5", "MYDOCU~6", "MYDOCU~7",
+            "MYDOCU~8", "MYDOCU~9",
+        ];
+        assert_eq!(
+            short_name("My Documents", &taken).unwrap().as_str(),
+            "MYDOC~10"
+        );
+        assert_eq!(
+            ShortName::from_long_name("My Documents", |_| true),
+            Err(crate::Error::InvalidArg)
+        );
+    }
+
+    #[test]
+    fn short_name_invalid() {
+        assert_eq!(short_name("", &[]), Err(crate::Error::InvalidPath));
+        assert_eq!(short_name("...", &[]), Err(crate::Error::InvalidPath));
+        assert_eq!(short_name("A/B", &[]), Err(crate::Error::InvalidPath));
+        assert_eq!(short_name("HD0:", &[]), Err(crate::Error::InvalidPath));
+        assert_eq!(short_name("A\tB", &[]), Err(crate::Error::InvalidPath));
+        assert_eq!(ShortName::new("MY FILE"), Err(crate::Error::InvalidPath));
+        assert_eq!(ShortName::new("MYFILE").unwrap().as_str(), "MYFILE");
+    }
+
+    #[test]
+    fn path_buf_push_pop() {
+        let mut path = PathBuf::<32>::new("HD0:").unwrap();
+        path.push("DOCS/").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/");
+        path.push("SALES.TXT").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/SALES.TXT");
+        path.push("SD0:/BOOTLDR").unwrap();
+        assert_eq!(path.as_str(), "SD0:/BOOTLDR");
+        assert!(path.pop());
+        assert_eq!(path.as_str(), "SD0:/");
+        assert!(!path.pop());
+        assert_eq!(path.as_str(), "SD0:/");
+
+        let mut path = PathBuf::<32>::new("DOCS/2023/JUNE/").unwrap();
+        assert!(path.pop());
+        assert_eq!(path.as_str(), "DOCS/2023/");
+        path.push("MAY").unwrap();
+        assert_eq!(path.as_str(), "DOCS/2023/MAY");
+        assert!(path.pop());
+        assert!(path.pop());
+        assert_eq!(path.as_str(), "DOCS/");
+        assert!(!path.pop());
+        assert_eq!(path.as_path().directory(), Some("DOCS"));
+    }
+
+    #[test]
+    fn path_buf_file_name() {
+        let mut path = PathBuf::<32>::new("HD0:/DOCS/SALES.TXT").unwrap();
+        path.set_file_name("REPORT.DOC").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/REPORT.DOC");
+        path.set_extension("BAK").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/REPORT.BAK");
+        path.set_extension("").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/REPORT");
+        path.set_extension("TXT").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/REPORT.TXT");
+
+        let mut path = PathBuf::<32>::new("HD0:").unwrap();
+        path.set_file_name("A.TXT").unwrap();
+        assert_eq!(path.as_str(), "HD0:/A.TXT");
+        let mut path = PathBuf::<32>::new("HD0:/DOCS/").unwrap();
+        assert_eq!(path.set_extension("TXT"), Err(crate::Error::InvalidPath));
+        path.set_file_name("A.TXT").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/A.TXT");
+    }
+
+    #[test]
+    fn path_buf_invalid() {
+        assert_eq!(
+            PathBuf::<32>::new("/DOCS").unwrap_err(),
+            crate::Error::InvalidPath
+        );
+        assert_eq!(
+            PathBuf::<8>::new("HD0:/DOCUMENTS").unwrap_err(),
+            crate::Error::InvalidArg
+        );
+        let mut path = PathBuf::<16>::new("HD0:/DOCS/").unwrap();
+        assert_eq!(path.push("SALES/JUNE.TXT"), Err(crate::Error::InvalidArg));
+        assert_eq!(path.push("A\tB"), Err(crate::Error::InvalidPath));
+        assert_eq!(path.set_file_name("A/B"), Err(crate::Error::InvalidPath));
+        assert_eq!(path.set_file_name("A:B"), Err(crate::Error::InvalidPath));
+        assert_eq!(path.as_str(), "HD0:/DOCS/");
+        assert_eq!(path.capacity(), 16);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn heap_path_buf() {
+        let mut path = HeapPathBuf::new("HD0:/DOCS").unwrap();
+        path.push("A VERY LONG DIRECTORY NAME/SALES.TXT").unwrap();
+        assert_eq!(
+            path.as_str(),
+            "HD0:/DOCS/A VERY LONG DIRECTORY NAME/SALES.TXT"
+        );
+        path.set_extension("XLS").unwrap();
+        assert!(path.pop());
+        assert_eq!(path.as_str(), "HD0:/DOCS/A VERY LONG DIRECTORY NAME/");
+        assert_eq!(path.push("HD0:/\u{7}"), Err(crate::Error::InvalidPath));
+        assert_eq!(
+            path.as_path().directory(),
+            Some("/DOCS/A VERY LONG DIRECTORY NAME")
+        );
+    }
+
+    #[test]
+    fn short_name_upper_case() {
+        assert_eq!(ShortName::new("readme.txt").unwrap().as_str(), "README.TXT");
+        assert_eq!(ShortName::new("Boot~1").unwrap().as_str(), "BOOT~1");
+    }
+
+    #[test]
+    fn set_file_name_rejects_special_names() {
+        let mut path = PathBuf::<32>::new("HD0:/DOCS/A.TXT").unwrap();
+        for name in ["", ".", "..", "A/B", "/", "HD1:B", ":"] {
+            assert_eq!(path.set_file_name(name), Err(crate::Error::InvalidPath));
+            assert_eq!(path.as_str(), "HD0:/DOCS/A.TXT");
+        }
+        // Dots are fine as part of a longer name
+        path.set_file_name("...X").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/...X");
+        #[cfg(feature = "alloc")]
+        {
+            let mut path = HeapPathBuf::new("HD0:/DOCS/").unwrap();
+            assert_eq!(path.set_file_name(".."), Err(crate::Error::InvalidPath));
+            assert_eq!(path.set_file_name("A:B"), Err(crate::Error::InvalidPath));
+            assert_eq!(path.as_str(), "HD0:/DOCS/");
+        }
+    }
+
+    #[test]
+    fn file_stem_leading_dot() {
+        let path = Path::new("HD0:/.profile").unwrap();
+        assert_eq!(path.file_stem(), Some(".profile"));
+        let path = Path::new("HD0:/.vimrc.bak").unwrap();
+        assert_eq!(path.file_stem(), Some(".vimrc"));
+        let path = Path::new("HD0:/DOCS/..").unwrap();
+        assert_eq!(path.file_stem(), Some(".."));
+    }
+
+    #[test]
+    fn iterators_clone() {
+        let path = Path::new("HD0:/DOCS/A.TXT").unwrap();
+        let mut ancestors = path.ancestors();
+        ancestors.next();
+        let copy = ancestors.clone();
+        assert_eq!(ancestors.count(), 2);
+        assert_eq!(copy.count(), 2);
+        let mut components = path.components();
+        components.next();
+        let copy = components.clone();
+        assert_eq!(components.count(), 3);
+        assert_eq!(copy.count(), 3);
+        assert!(std::format!("{:?}", path.ancestors()).starts_with("Ancestors"));
+    }
+
+    #[test]
+    fn wildcard_empty_extension() {
+        assert!(wildcard_match("*.", "README"));
+        assert!(wildcard_match("READ??.", "readme"));
+        assert!(!wildcard_match("*.", "README.TXT"));
+        assert!(!wildcard_match("README.", "READMEX"));
+        // A name which really ends in `.` still matches
+        assert!(wildcard_match("*.", "README."));
+    }
+
+    #[test]
+    fn set_extension_dot_files() {
+        let mut path = PathBuf::<32>::new("HD0:/DOCS/.PROFILE").unwrap();
+        path.set_extension("").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/.PROFILE");
+        path.set_extension("BAK").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/.PROFILE.BAK");
+        path.set_extension("OLD").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/.PROFILE.OLD");
+        path.set_extension("").unwrap();
+        assert_eq!(path.as_str(), "HD0:/DOCS/.PROFILE");
+        #[cfg(feature = "alloc")]
+        {
+            let mut path = HeapPathBuf::new("..X").unwrap();
+            path.set_extension("TXT").unwrap();
+            assert_eq!(path.as_str(), "..X.TXT");
+            path.set_extension("").unwrap();
+            assert_eq!(path.as_str(), "..X");
+        }
+    }
+
+    #[test]
+    fn stem_and_extension_agree() {
+        let cases = [
+            ("HD0:/DOCS/.PROFILE", ".PROFILE", None),
+            ("HD0:/DOCS/.PROFILE.BAK", ".PROFILE", Some("BAK")),
+            ("HD0:/DOCS/README.TXT", "README", Some("TXT")),
+            ("HD0:/DOCS/A.B.C", "A.B", Some("C")),
+            ("HD0:/DOCS/README", "README", None),
+            ("HD0:/DOCS/README.", "README", Some("")),
+            ("HD0:/DOCS/..", "..", None),
+            ("...X", "...X", None),
+        ];
+        for (path_str, stem, extension) in cases {
+            let path = Path::new(path_str).unwrap();
+            assert_eq!(path.file_stem(), Some(stem), "{path_str}");
+            assert_eq!(path.extension(), extension, "{path_str}");
+            // Putting them back together gives the filename again
+            let mut rebuilt = std::string::String::from(stem);
+            if let Some(extension) = extension {
+                rebuilt.push('.');
+                rebuilt.push_str(extension);
+            }
+            assert_eq!(Some(rebuilt.as_str()), path.filename(), "{path_str}");
+        }
     }
 }
 
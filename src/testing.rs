@@ -0,0 +1,940 @@
+//! A fake Neotron OS, for testing the helpers which call the [`Api`].
+//!
+//! The filesystem lives in memory, in a thread-local, so each test gets its
+//! own. Paths are case-insensitive, like on FAT.
+
+// ============================================================================
+// Imports
+// ============================================================================
+
+extern crate std;
+
+use std::{cell::RefCell, collections::BTreeMap, string::String, vec::Vec};
+
+use crate::{dir, drive, file, path, Api, Error, FfiBuffer, FfiByteSlice, FfiString, Result};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// The maximum length of any path in the fake filesystem
+const MAX_PATH_LEN: usize = 128;
+
+/// The size of a cluster on the fake disk
+const CLUSTER_SIZE: u64 = 512;
+
+/// The size of each fake disk
+const DISK_SIZE: u64 = 32 * 1024 * 1024;
+
+/// The block devices which can be mounted
+const BLOCK_DEVICES: [&str; 2] = ["SDCARD0", "SDCARD1"];
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A file or directory in the fake filesystem
+struct Node {
+    /// File attributes
+    attr: file::Attributes,
+    /// The contents, if this is a file
+    data: Vec<u8>,
+    /// When the node was created
+    ctime: file::Time,
+    /// When the node was last modified
+    mtime: file::Time,
+}
+
+impl Node {
+    /// Make a new node, modified at the fake OS's idea of "now"
+    fn new(attr: file::Attributes) -> Node {
+        let now = file::Time::new(2023, 6, 30, 12, 0, 0).unwrap();
+        Node {
+            attr,
+            data: Vec::new(),
+            ctime: now,
+            mtime: now,
+        }
+    }
+
+    /// Is this node a directory?
+    fn is_dir(&self) -> bool {
+        self.attr.contains(file::Attributes::DIRECTORY)
+    }
+}
+
+/// An open directory
+struct OpenDir {
+    /// The key of the directory in [`FakeOs::nodes`]
+    key: String,
+    /// How many entries have been read
+    position: usize,
+}
+
+/// An open file
+struct OpenFile {
+    /// The key of the file in [`FakeOs::nodes`]
+    key: String,
+    /// Should the file be deleted when it is closed?
+    delete_on_close: bool,
+}
+
+/// A lock held on part of an open file
+struct Lock {
+    /// The file handle which holds the lock
+    handle: u8,
+    /// Whether other handles may share it
+    kind: file::LockKind,
+    /// Which bytes are locked
+    range: file::LockRange,
+}
+
+/// The state of the fake OS
+#[derive(Default)]
+struct FakeOs {
+    /// Every file and directory, keyed by upper-case absolute path with no
+    /// trailing `/`
+    nodes: BTreeMap<String, Node>,
+    /// The current directory
+    cwd: String,
+    /// Open directories, keyed by handle
+    dirs: BTreeMap<u8, OpenDir>,
+    /// Open files, keyed by handle
+    files: BTreeMap<u8, OpenFile>,
+    /// Locks held on open files
+    locks: Vec<Lock>,
+    /// The filesystem on each drive added with `mount`
+    mounted: BTreeMap<String, drive::FsType>,
+    /// Aliases added with `assign`, giving the key each one refers to
+    aliases: BTreeMap<String, String>,
+    /// Operations on this key fail with `Error::DeviceSpecific`
+    broken: Option<String>,
+}
+
+impl FakeOs {
+    /// Turn a path from the API into a key for [`FakeOs::nodes`]
+    fn key(&self, path: &str) -> core::result::Result<String, Error> {
+        let path = path::Path::new(path)?;
+        let cwd = path::Path::new(&self.cwd)?;
+        let resolved: path::PathBuf<MAX_PATH_LEN> = path.resolve(&cwd)?;
+        let mut key = resolved.as_str().to_ascii_uppercase();
+        if key.ends_with('/') {
+            key.pop();
+        }
+        let (drive, rest) = key.split_once(':').unwrap();
+        if let Some(target) = self.aliases.get(drive) {
+            key = target.clone() + rest;
+        }
+        Ok(key)
+    }
+
+    /// Check the name of a drive or alias, and remove any trailing `:`
+    fn drive_key(name: &str) -> core::result::Result<String, Error> {
+        let name = name.strip_suffix(':').unwrap_or(name);
+        if name.is_empty() || name.len() > crate::MAX_DRIVE_NAME_LEN || name.contains([':', '/']) {
+            return Err(Error::InvalidArg);
+        }
+        Ok(name.to_ascii_uppercase())
+    }
+
+    /// Is anything open on the given drive, or does an alias refer to it?
+    fn drive_in_use(&self, drive: &str) -> bool {
+        let on_drive = |key: &String| key.split(':').next() == Some(drive);
+        self.files.values().any(|f| on_drive(&f.key))
+            || self.dirs.values().any(|d| on_drive(&d.key))
+            || self.aliases.values().any(on_drive)
+    }
+
+    /// Get the keys of everything inside the given directory
+    fn children(&self, key: &str) -> Vec<String> {
+        self.nodes
+            .keys()
+            .filter(|child| parent_key(child) == Some(key))
+            .cloned()
+            .collect()
+    }
+
+    /// Get the name and key of everything in a directory, in the order
+    /// `readdir` returns them.
+    ///
+    /// Sub-directories have `.` and `..` entries, like on FAT.
+    fn listing(&self, key: &str) -> Vec<(String, String)> {
+        let mut names = Vec::new();
+        if let Some(parent) = parent_key(key) {
+            names.push((String::from("."), String::from(key)));
+            names.push((String::from(".."), String::from(parent)));
+        }
+        for child in self.children(key) {
+            let (_parent, name) = child.rsplit_once('/').unwrap();
+            names.push((String::from(name), child.clone()));
+        }
+        names
+    }
+
+    /// Find a free handle number
+    fn free_handle<T>(handles: &BTreeMap<u8, T>) -> core::result::Result<u8, Error> {
+        (0..=u8::MAX)
+            .find(|handle| !handles.contains_key(handle))
+            .ok_or(Error::OutOfMemory)
+    }
+
+    /// Build the properties for the node with the given key
+    fn stat(&self, key: &str) -> file::Stat {
+        let node = &self.nodes[key];
+        let atime = node.mtime;
+        file::Stat {
+            file_size: node.data.len() as u64,
+            ctime: node.ctime,
+            mtime: node.mtime,
+            attr: node.attr,
+            atime: file::Time::new(atime.year(), atime.month(), atime.day(), 0, 0, 0).unwrap(),
+            allocated_size: (node.data.len() as u64).next_multiple_of(CLUSTER_SIZE),
+            drive: self.drive_name(key),
+        }
+    }
+
+    /// Get the names of every drive, in order
+    fn drives(&self) -> impl Iterator<Item = &str> {
+        self.nodes.keys().filter_map(|key| key.strip_suffix(':'))
+    }
+
+    /// Get the padded name of the drive a key is on
+    fn drive_name(&self, key: &str) -> [u8; crate::MAX_DRIVE_NAME_LEN] {
+        let name = key.split(':').next().unwrap();
+        let mut result = [0u8; crate::MAX_DRIVE_NAME_LEN];
+        result[..name.len()].copy_from_slice(name.as_bytes());
+        result
+    }
+
+    /// Fail if the given key has been marked as broken
+    fn check_broken(&self, key: &str) -> core::result::Result<(), Error> {
+        if self.broken.as_deref() == Some(key) {
+            Err(Error::DeviceSpecific)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Take a lock, failing with `Error::WouldBlock` if another handle holds
+    /// a lock which conflicts.
+    fn try_lock(
+        &mut self,
+        handle: u8,
+        kind: file::LockKind,
+        range: file::LockRange,
+    ) -> core::result::Result<(), Error> {
+        let key = &self.files.get(&handle).ok_or(Error::BadHandle)?.key;
+        let conflict = self.locks.iter().any(|lock| {
+            lock.handle != handle
+                && self.files[&lock.handle].key == *key
+                && lock.range.overlaps(&range)
+                && (kind == file::LockKind::Exclusive || lock.kind == file::LockKind::Exclusive)
+        });
+        if conflict {
+            return Err(Error::WouldBlock);
+        }
+        // Our own locks on these bytes are replaced
+        self.unlock(handle, range)?;
+        if range.length != 0 {
+            self.locks.push(Lock {
+                handle,
+                kind,
+                range,
+            });
+        }
+        Ok(())
+    }
+
+    /// Release a handle's locks on the given bytes, shortening or splitting
+    /// any locks which only partly overlap.
+    fn unlock(&mut self, handle: u8, range: file::LockRange) -> core::result::Result<(), Error> {
+        if !self.files.contains_key(&handle) {
+            return Err(Error::BadHandle);
+        }
+        let mut kept = Vec::new();
+        for lock in self.locks.drain(..) {
+            if lock.handle != handle || !lock.range.overlaps(&range) {
+                kept.push(lock);
+                continue;
+            }
+            if lock.range.offset < range.offset {
+                let before =
+                    file::LockRange::new(lock.range.offset, range.offset - lock.range.offset);
+                kept.push(Lock {
+                    range: before,
+                    ..lock
+                });
+            }
+            if lock.range.end() > range.end() {
+                let after = file::LockRange::new(range.end(), lock.range.end() - range.end());
+                kept.push(Lock {
+                    range: after,
+                    ..lock
+                });
+            }
+        }
+        self.locks = kept;
+        Ok(())
+    }
+
+    /// Check there is a directory which could hold the given key
+    fn check_parent(&self, key: &str) -> core::result::Result<(), Error> {
+        let parent = parent_key(key).ok_or(Error::InvalidPath)?;
+        match self.nodes.get(parent) {
+            Some(node) if node.is_dir() => Ok(()),
+            _ => Err(Error::NotFound),
+        }
+    }
+}
+
+// ============================================================================
+// Functions
+// ============================================================================
+
+std::thread_local! {
+    static OS: RefCell<FakeOs> = RefCell::new(FakeOs::default());
+}
+
+/// Reset the fake filesystem so it contains just the given paths.
+///
+/// Paths ending in `/` are directories, and everything else is an empty file.
+/// Parent directories are created automatically. The current directory is
+/// set to `HD0:/`.
+pub(crate) fn reset(paths: &[&str]) {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        *os = FakeOs::default();
+        os.cwd = String::from("HD0:/");
+        for path in paths {
+            let key = os.key(path).unwrap();
+            let attr = if path.ends_with('/') {
+                file::Attributes::DIRECTORY
+            } else {
+                file::Attributes::ARCHIVE
+            };
+            let mut parent = parent_key(&key);
+            while let Some(dir) = parent {
+                os.nodes
+                    .entry(String::from(dir))
+                    .or_insert(Node::new(file::Attributes::DIRECTORY));
+                parent = parent_key(dir);
+            }
+            os.nodes.insert(key, Node::new(attr));
+        }
+    });
+}
+
+/// Make every operation which changes the given path fail, with
+/// `Error::DeviceSpecific`.
+pub(crate) fn set_broken(path: &str) {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        os.broken = Some(os.key(path).unwrap());
+    });
+}
+
+/// Get the key of every file and directory, in order.
+pub(crate) fn paths() -> Vec<String> {
+    OS.with(|os| os.borrow().nodes.keys().cloned().collect())
+}
+
+/// How many handles are currently open?
+pub(crate) fn open_handles() -> usize {
+    OS.with(|os| {
+        let os = os.borrow();
+        os.dirs.len() + os.files.len()
+    })
+}
+
+/// Get an [`Api`] which talks to the fake OS.
+pub(crate) fn api() -> Api {
+    Api {
+        open,
+        close,
+        write,
+        read,
+        seek_set,
+        seek_cur,
+        seek_end,
+        rename,
+        ioctl,
+        opendir,
+        closedir,
+        readdir,
+        stat,
+        fstat,
+        deletefile,
+        deletedir,
+        chdir,
+        dchdir,
+        pwd,
+        malloc,
+        free,
+        get_timezone,
+        set_timezone,
+        readdir_long,
+        list_drives,
+        volume_info,
+        mount,
+        unmount,
+        assign,
+        unassign,
+        rewinddir,
+        telldir,
+        seekdir,
+        readdir_many,
+        copy_file,
+        splice,
+        mkdir,
+        lock,
+        try_lock,
+        unlock,
+        set_attributes,
+        set_times,
+    }
+}
+
+/// Get the key of the directory containing the given key.
+///
+/// Drive roots (like `HD0:`) have no parent.
+fn parent_key(key: &str) -> Option<&str> {
+    let (parent, _name) = key.rsplit_once('/')?;
+    Some(parent)
+}
+
+extern "C" fn open(path: FfiString, flags: file::Flags) -> Result<file::Handle> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        let already_open = os.files.values().any(|f| f.key == key);
+        match os.nodes.get_mut(&key) {
+            Some(node) if node.is_dir() => return Err(Error::InvalidPath),
+            Some(_) if already_open && flags.contains(file::Flags::TRUNCATE) => {
+                return Err(Error::InvalidArg)
+            }
+            Some(_) if flags.contains(file::Flags::CREATE_NEW) => return Err(Error::AlreadyExists),
+            Some(node) if flags.contains(file::Flags::TRUNCATE) => node.data.clear(),
+            Some(_) => {}
+            None if flags.intersects(file::Flags::CREATE | file::Flags::CREATE_NEW) => {
+                os.check_parent(&key)?;
+                os.nodes
+                    .insert(key.clone(), Node::new(file::Attributes::ARCHIVE));
+            }
+            None => return Err(Error::NotFound),
+        }
+        let handle = FakeOs::free_handle(&os.files)?;
+        os.files.insert(
+            handle,
+            OpenFile {
+                key,
+                delete_on_close: flags.contains(file::Flags::DELETE_ON_CLOSE),
+            },
+        );
+        Ok(file::Handle::new(handle))
+    })
+    .into()
+}
+
+extern "C" fn close(fd: file::Handle) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let open_file = os.files.remove(&fd.value()).ok_or(Error::BadHandle)?;
+        os.locks.retain(|lock| lock.handle != fd.value());
+        let mut still_open = false;
+        for other in os.files.values_mut().filter(|f| f.key == open_file.key) {
+            // Whoever closes it last does the deleting
+            other.delete_on_close |= open_file.delete_on_close;
+            still_open = true;
+        }
+        if open_file.delete_on_close && !still_open {
+            os.nodes.remove(&open_file.key);
+        }
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn write(_fd: file::Handle, _buffer: FfiByteSlice) -> Result<()> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn read(_fd: file::Handle, _buffer: FfiBuffer) -> Result<usize> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn seek_set(_fd: file::Handle, _position: u64) -> Result<()> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn seek_cur(_fd: file::Handle, _offset: i64) -> Result<u64> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn seek_end(_fd: file::Handle) -> Result<u64> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn rename(old_path: FfiString, new_path: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let old_key = os.key(old_path.as_str())?;
+        let new_key = os.key(new_path.as_str())?;
+        if !os.nodes.contains_key(&old_key) {
+            return Err(Error::NotFound);
+        }
+        if os.nodes.contains_key(&new_key) {
+            return Err(Error::AlreadyExists);
+        }
+        if os.drive_name(&old_key) != os.drive_name(&new_key) {
+            return Err(Error::InvalidArg);
+        }
+        os.check_parent(&new_key)?;
+        let prefix = old_key.clone() + "/";
+        let moving: Vec<String> = os
+            .nodes
+            .keys()
+            .filter(|k| **k == old_key || k.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for key in moving {
+            let node = os.nodes.remove(&key).unwrap();
+            os.nodes
+                .insert(new_key.clone() + &key[old_key.len()..], node);
+        }
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn ioctl(_fd: file::Handle, _command: u64, _value: u64) -> Result<u64> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn opendir(path: FfiString) -> Result<dir::Handle> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        match os.nodes.get(&key) {
+            Some(node) if node.attr.contains(file::Attributes::DIRECTORY) => {}
+            Some(_) => return Err(Error::InvalidPath),
+            None => return Err(Error::NotFound),
+        }
+        let handle = FakeOs::free_handle(&os.dirs)?;
+        os.dirs.insert(handle, OpenDir { key, position: 0 });
+        Ok(dir::Handle::new(handle))
+    })
+    .into()
+}
+
+extern "C" fn closedir(dir: dir::Handle) -> Result<()> {
+    OS.with(|os| match os.borrow_mut().dirs.remove(&dir.value()) {
+        Some(_) => Result::Ok(()),
+        None => Result::Err(Error::BadHandle),
+    })
+}
+
+extern "C" fn readdir(dir: dir::Handle) -> Result<dir::Entry> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let open_dir = os.dirs.get(&dir.value()).ok_or(Error::BadHandle)?;
+        let listing = os.listing(&open_dir.key);
+        let position = open_dir.position;
+        let (name, key) = listing.get(position).ok_or(Error::EndOfFile)?;
+        let properties = os.stat(key);
+        os.dirs.get_mut(&dir.value()).unwrap().position += 1;
+        dir::Entry::new(name, properties)
+    })
+    .into()
+}
+
+extern "C" fn stat(path: FfiString) -> Result<file::Stat> {
+    OS.with(|os| {
+        let os = os.borrow();
+        let key = os.key(path.as_str())?;
+        if os.nodes.contains_key(&key) {
+            Ok(os.stat(&key))
+        } else {
+            Err(Error::NotFound)
+        }
+    })
+    .into()
+}
+
+extern "C" fn fstat(_fd: file::Handle) -> Result<file::Stat> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn deletefile(path: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        os.check_broken(&key)?;
+        match os.nodes.get(&key) {
+            Some(node) if node.is_dir() => Err(Error::InvalidPath),
+            Some(node) if node.attr.contains(file::Attributes::READ_ONLY) => {
+                Err(Error::FileReadOnly)
+            }
+            Some(_) => {
+                os.nodes.remove(&key);
+                Ok(())
+            }
+            None => Err(Error::NotFound),
+        }
+    })
+    .into()
+}
+
+extern "C" fn deletedir(path: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        os.check_broken(&key)?;
+        match os.nodes.get(&key) {
+            Some(node) if !node.is_dir() => return Err(Error::InvalidPath),
+            Some(node) if node.attr.contains(file::Attributes::READ_ONLY) => {
+                return Err(Error::FileReadOnly)
+            }
+            Some(_) => {}
+            None => return Err(Error::NotFound),
+        }
+        if parent_key(&key).is_none() || !os.children(&key).is_empty() {
+            return Err(Error::InvalidArg);
+        }
+        os.nodes.remove(&key);
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn chdir(path: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        match os.nodes.get(&key) {
+            Some(node) if node.attr.contains(file::Attributes::DIRECTORY) => {
+                os.cwd = key + "/";
+                Ok(())
+            }
+            _ => Err(Error::NotFound),
+        }
+    })
+    .into()
+}
+
+extern "C" fn dchdir(_dir: dir::Handle) -> Result<()> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn pwd(mut path: FfiBuffer) -> Result<usize> {
+    OS.with(|os| {
+        let os = os.borrow();
+        let buffer = path.as_mut_slice().ok_or(Error::InvalidArg)?;
+        let output = buffer.get_mut(..os.cwd.len()).ok_or(Error::InvalidArg)?;
+        output.copy_from_slice(os.cwd.as_bytes());
+        Ok(os.cwd.len())
+    })
+    .into()
+}
+
+extern "C" fn malloc(_size: usize, _alignment: usize) -> Result<*mut core::ffi::c_void> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn free(_ptr: *mut core::ffi::c_void, _size: usize, _alignment: usize) {}
+
+extern "C" fn get_timezone() -> Result<file::TimeZone> {
+    Result::Ok(file::TimeZone::UTC)
+}
+
+extern "C" fn set_timezone(_tz: file::TimeZone) -> Result<()> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn readdir_long(_dir: dir::Handle, _long_name: FfiBuffer) -> Result<dir::LongEntry> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn list_drives(index: usize, mut name: FfiBuffer) -> Result<usize> {
+    OS.with(|os| {
+        let os = os.borrow();
+        let drive = os.drives().nth(index).ok_or(Error::EndOfFile)?;
+        let buffer = name.as_mut_slice().ok_or(Error::InvalidArg)?;
+        let output = buffer.get_mut(..drive.len()).ok_or(Error::InvalidArg)?;
+        output.copy_from_slice(drive.as_bytes());
+        Ok(drive.len())
+    })
+    .into()
+}
+
+extern "C" fn volume_info(drive: FfiString) -> Result<drive::VolumeInfo> {
+    OS.with(|os| {
+        let os = os.borrow();
+        let wanted = drive.as_str().trim_end_matches(':').to_ascii_uppercase();
+        let name = os
+            .drives()
+            .find(|name| *name == wanted)
+            .ok_or(Error::NotFound)?;
+        let mut label = [0u8; crate::MAX_VOLUME_LABEL_LEN];
+        label[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(drive::VolumeInfo {
+            fs_type: os
+                .mounted
+                .get(name)
+                .copied()
+                .unwrap_or(drive::FsType::Fat32),
+            total_bytes: DISK_SIZE,
+            free_bytes: DISK_SIZE,
+            cluster_size: CLUSTER_SIZE as u32,
+            label,
+            serial_number: 0x1234_5678,
+        })
+    })
+    .into()
+}
+
+extern "C" fn mount(device: FfiString, drive: FfiString, fs_type: drive::FsType) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let name = FakeOs::drive_key(drive.as_str())?;
+        let fs_type = match fs_type {
+            drive::FsType::Unknown | drive::FsType::Device => return Err(Error::InvalidArg),
+            // Everything we detect is FAT32
+            drive::FsType::Auto => drive::FsType::Fat32,
+            fs_type => fs_type,
+        };
+        if os.drives().any(|d| d == name) || os.aliases.contains_key(&name) {
+            return Err(Error::AlreadyExists);
+        }
+        if device.as_str().contains(':') {
+            // A disk image file
+            let key = os.key(device.as_str())?;
+            match os.nodes.get(&key) {
+                Some(node) if node.is_dir() => return Err(Error::InvalidPath),
+                Some(_) => {}
+                None => return Err(Error::NotFound),
+            }
+            if os.files.values().any(|f| f.key == key) {
+                return Err(Error::InvalidArg);
+            }
+        } else if !BLOCK_DEVICES
+            .iter()
+            .any(|d| d.eq_ignore_ascii_case(device.as_str()))
+        {
+            return Err(Error::NotFound);
+        }
+        os.nodes
+            .insert(name.clone() + ":", Node::new(file::Attributes::DIRECTORY));
+        os.mounted.insert(name, fs_type);
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn unmount(drive: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let name = FakeOs::drive_key(drive.as_str())?;
+        let root = name.clone() + ":";
+        if !os.nodes.contains_key(&root) {
+            return Err(Error::NotFound);
+        }
+        if os.drive_in_use(&name) {
+            return Err(Error::InvalidArg);
+        }
+        let prefix = root.clone() + "/";
+        os.nodes
+            .retain(|key, _| *key != root && !key.starts_with(&prefix));
+        os.mounted.remove(&name);
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn assign(alias: FfiString, target: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let name = FakeOs::drive_key(alias.as_str())?;
+        if os.drives().any(|d| d == name) {
+            return Err(Error::AlreadyExists);
+        }
+        if !path::Path::new(target.as_str())?.is_absolute_path() {
+            return Err(Error::InvalidPath);
+        }
+        let key = os.key(target.as_str())?;
+        match os.nodes.get(&key) {
+            Some(node) if node.is_dir() => {}
+            Some(_) => return Err(Error::InvalidPath),
+            None => return Err(Error::NotFound),
+        }
+        os.aliases.insert(name, key);
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn unassign(alias: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let name = FakeOs::drive_key(alias.as_str())?;
+        match os.aliases.remove(&name) {
+            Some(_) => Ok(()),
+            None => Err(Error::NotFound),
+        }
+    })
+    .into()
+}
+
+extern "C" fn rewinddir(dir: dir::Handle) -> Result<()> {
+    seekdir(dir, dir::Position::new(0))
+}
+
+extern "C" fn telldir(dir: dir::Handle) -> Result<dir::Position> {
+    OS.with(|os| match os.borrow().dirs.get(&dir.value()) {
+        Some(open_dir) => Result::Ok(dir::Position::new(open_dir.position as u64)),
+        None => Result::Err(Error::BadHandle),
+    })
+}
+
+extern "C" fn seekdir(dir: dir::Handle, position: dir::Position) -> Result<()> {
+    OS.with(|os| match os.borrow_mut().dirs.get_mut(&dir.value()) {
+        Some(open_dir) => {
+            open_dir.position = position.value() as usize;
+            Result::Ok(())
+        }
+        None => Result::Err(Error::BadHandle),
+    })
+}
+
+extern "C" fn readdir_many(dir: dir::Handle, mut buffer: dir::EntryBuffer) -> Result<usize> {
+    let Some(entries) = buffer.as_mut_slice() else {
+        return Result::Err(Error::InvalidArg);
+    };
+    let mut count = 0;
+    for slot in entries.iter_mut() {
+        match readdir(dir) {
+            Result::Ok(entry) => *slot = entry,
+            Result::Err(Error::EndOfFile) if count > 0 => break,
+            Result::Err(e) => return Result::Err(e),
+        }
+        count += 1;
+    }
+    Result::Ok(count)
+}
+
+extern "C" fn copy_file(
+    old_path: FfiString,
+    new_path: FfiString,
+    flags: file::CopyFlags,
+) -> Result<u64> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let old_key = os.key(old_path.as_str())?;
+        let new_key = os.key(new_path.as_str())?;
+        os.check_broken(&old_key)?;
+        os.check_broken(&new_key)?;
+        let source = match os.nodes.get(&old_key) {
+            Some(node) if node.is_dir() => return Err(Error::InvalidPath),
+            Some(node) => node,
+            None => return Err(Error::NotFound),
+        };
+        let mut copy = Node::new(file::Attributes::ARCHIVE);
+        copy.data = source.data.clone();
+        if flags.contains(file::CopyFlags::PRESERVE_ATTRIBUTES) {
+            copy.attr = source.attr;
+        }
+        if flags.contains(file::CopyFlags::PRESERVE_TIMES) {
+            copy.ctime = source.ctime;
+            copy.mtime = source.mtime;
+        } else {
+            copy.ctime = file::Time::new(2024, 1, 1, 0, 0, 0).unwrap();
+            copy.mtime = copy.ctime;
+        }
+        match os.nodes.get(&new_key) {
+            Some(node) if node.is_dir() => return Err(Error::InvalidPath),
+            Some(_) if !flags.contains(file::CopyFlags::OVERWRITE) => {
+                return Err(Error::AlreadyExists)
+            }
+            _ => {}
+        }
+        os.check_parent(&new_key)?;
+        let len = copy.data.len() as u64;
+        os.nodes.insert(new_key, copy);
+        Ok(len)
+    })
+    .into()
+}
+
+extern "C" fn splice(_src: file::Handle, _dst: file::Handle, _length: u64) -> Result<u64> {
+    Result::Err(Error::Unimplemented)
+}
+
+extern "C" fn mkdir(path: FfiString) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        os.check_broken(&key)?;
+        if os.nodes.contains_key(&key) {
+            return Err(Error::AlreadyExists);
+        }
+        os.check_parent(&key)?;
+        os.nodes.insert(key, Node::new(file::Attributes::DIRECTORY));
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn lock(fd: file::Handle, kind: file::LockKind, range: file::LockRange) -> Result<()> {
+    // Nothing else runs while we wait, so a conflicting lock would never be
+    // released. Fail instead of hanging the test.
+    try_lock(fd, kind, range)
+}
+
+extern "C" fn try_lock(
+    fd: file::Handle,
+    kind: file::LockKind,
+    range: file::LockRange,
+) -> Result<()> {
+    OS.with(|os| os.borrow_mut().try_lock(fd.value(), kind, range))
+        .into()
+}
+
+extern "C" fn unlock(fd: file::Handle, range: file::LockRange) -> Result<()> {
+    OS.with(|os| os.borrow_mut().unlock(fd.value(), range))
+        .into()
+}
+
+extern "C" fn set_attributes(path: FfiString, attr: file::Attributes) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        os.check_broken(&key)?;
+        let node = os.nodes.get_mut(&key).ok_or(Error::NotFound)?;
+        let changeable = file::Attributes::READ_ONLY
+            | file::Attributes::HIDDEN
+            | file::Attributes::SYSTEM
+            | file::Attributes::ARCHIVE;
+        node.attr = (node.attr - changeable) | (attr & changeable);
+        Ok(())
+    })
+    .into()
+}
+
+extern "C" fn set_times(path: FfiString, ctime: file::Time, mtime: file::Time) -> Result<()> {
+    OS.with(|os| {
+        let mut os = os.borrow_mut();
+        let key = os.key(path.as_str())?;
+        os.check_broken(&key)?;
+        if !ctime.is_valid() || !mtime.is_valid() {
+            return Err(Error::InvalidArg);
+        }
+        let node = os.nodes.get_mut(&key).ok_or(Error::NotFound)?;
+        node.ctime = ctime;
+        node.mtime = mtime;
+        Ok(())
+    })
+    .into()
+}
+
+// ============================================================================
+// End of File
+// ============================================================================